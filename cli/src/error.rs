@@ -0,0 +1,91 @@
+// Copyright (c) The dgc.network
+// SPDX-License-Identifier: Apache-2.0
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+
+use dgc_core_common::error::{QueryError, TransactionError};
+use protobuf::ProtobufError;
+use reqwest;
+use sawtooth_sdk::signing;
+
+#[derive(Debug)]
+pub enum CliError {
+    //Bad command line input
+    UserError(String),
+    IoError(io::Error),
+    SigningError(signing::Error),
+    ProtobufError(ProtobufError),
+    TransactionError(TransactionError),
+    QueryError(QueryError),
+    HttpError(reqwest::Error),
+    //The REST API answered with something other than what was asked for
+    RestApiError(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CliError::UserError(ref msg) => write!(f, "{}", msg),
+            CliError::IoError(ref err) => write!(f, "IoError: {}", err),
+            CliError::SigningError(ref err) => write!(f, "SigningError: {}", err),
+            CliError::ProtobufError(ref err) => write!(f, "ProtobufError: {}", err),
+            CliError::TransactionError(ref err) => write!(f, "TransactionError: {}", err),
+            CliError::QueryError(ref err) => write!(f, "QueryError: {}", err),
+            CliError::HttpError(ref err) => write!(f, "HttpError: {}", err),
+            CliError::RestApiError(ref msg) => write!(f, "RestApiError: {}", msg),
+        }
+    }
+}
+
+impl StdError for CliError {
+    fn description(&self) -> &str {
+        match *self {
+            CliError::UserError(ref msg) => msg,
+            CliError::IoError(ref err) => err.description(),
+            CliError::SigningError(ref err) => err.description(),
+            CliError::ProtobufError(ref err) => err.description(),
+            CliError::TransactionError(ref err) => err.description(),
+            CliError::QueryError(ref err) => err.description(),
+            CliError::HttpError(ref err) => err.description(),
+            CliError::RestApiError(ref msg) => msg,
+        }
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> Self {
+        CliError::IoError(e)
+    }
+}
+
+impl From<signing::Error> for CliError {
+    fn from(e: signing::Error) -> Self {
+        CliError::SigningError(e)
+    }
+}
+
+impl From<ProtobufError> for CliError {
+    fn from(e: ProtobufError) -> Self {
+        CliError::ProtobufError(e)
+    }
+}
+
+impl From<TransactionError> for CliError {
+    fn from(e: TransactionError) -> Self {
+        CliError::TransactionError(e)
+    }
+}
+
+impl From<QueryError> for CliError {
+    fn from(e: QueryError) -> Self {
+        CliError::QueryError(e)
+    }
+}
+
+impl From<reqwest::Error> for CliError {
+    fn from(e: reqwest::Error) -> Self {
+        CliError::HttpError(e)
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright (c) The dgc.network
+// SPDX-License-Identifier: Apache-2.0
+
+use std::env;
+use std::fs;
+use std::fs::{File, OpenOptions, Permissions};
+use std::io::prelude::*;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::PathBuf;
+
+use dirs;
+use sawtooth_sdk::signing;
+use sawtooth_sdk::signing::secp256k1::Secp256k1PrivateKey;
+
+use error::CliError;
+
+//Keys live where the sawtooth CLI keeps them, ~/.sawtooth/keys/<name>.priv
+fn key_dir() -> Result<PathBuf, CliError> {
+    match dirs::home_dir() {
+        Some(home) => Ok(home.join(".sawtooth").join("keys")),
+        None => Err(CliError::UserError(String::from("Unable to find home directory"))),
+    }
+}
+
+fn default_key_name() -> String {
+    env::var("USER").unwrap_or_else(|_| String::from("dgc-core"))
+}
+
+//Generates a secp256k1 key pair and writes it as hex to <name>.priv and
+//<name>.pub, returning the public key
+pub fn generate_key(name: Option<&str>, force: bool) -> Result<String, CliError> {
+    let name = name.map(String::from).unwrap_or_else(default_key_name);
+    let dir = key_dir()?;
+    let private_key_path = dir.join(format!("{}.priv", name));
+    let public_key_path = dir.join(format!("{}.pub", name));
+    if !force && (private_key_path.exists() || public_key_path.exists()) {
+        return Err(CliError::UserError(format!(
+            "Key {} already exists, use --force to overwrite it",
+            private_key_path.display()
+        )));
+    }
+
+    let context = signing::create_context("secp256k1")?;
+    let private_key = context.new_random_private_key()?;
+    let public_key = context.get_public_key(&*private_key)?;
+
+    fs::create_dir_all(&dir)?;
+    //Only the owner can read the private key, also when overwriting an existing one
+    let mut private_key_file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&private_key_path)?;
+    private_key_file.set_permissions(Permissions::from_mode(0o600))?;
+    private_key_file.write_all(format!("{}\n", private_key.as_hex()).as_bytes())?;
+    File::create(&public_key_path)?.write_all(format!("{}\n", public_key.as_hex()).as_bytes())?;
+
+    Ok(public_key.as_hex())
+}
+
+//Reads a private key from a file path, or from ~/.sawtooth/keys/<user>.priv
+pub fn load_signing_key(path: Option<&str>) -> Result<Secp256k1PrivateKey, CliError> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => key_dir()?.join(format!("{}.priv", default_key_name())),
+    };
+    let mut contents = String::new();
+    File::open(&path)
+        .map_err(|err| CliError::UserError(format!("Unable to read key {}: {}", path.display(), err)))?
+        .read_to_string(&mut contents)?;
+    Ok(Secp256k1PrivateKey::from_hex(contents.trim())?)
+}
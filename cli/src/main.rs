@@ -0,0 +1,252 @@
+// Copyright (c) The dgc.network
+// SPDX-License-Identifier: Apache-2.0
+
+#[macro_use]
+extern crate clap;
+extern crate dgc_core_common;
+extern crate dirs;
+extern crate protobuf;
+extern crate reqwest;
+extern crate sawtooth_sdk;
+extern crate serde_json;
+
+mod error;
+mod key;
+mod rest;
+
+use std::process;
+
+use clap::ArgMatches;
+use sawtooth_sdk::signing::secp256k1::Secp256k1Context;
+use sawtooth_sdk::signing::Context;
+
+use dgc_core_common::client::{QueryClient, DEFAULT_URL};
+use dgc_core_common::payload::Action;
+use dgc_core_common::protos::payload;
+use dgc_core_common::transaction::TransactionBuilder;
+use error::CliError;
+
+fn main() {
+    let matches = clap_app!(dgc_core =>
+        (version: crate_version!())
+        (about: "dgc-core wallet (Rust)")
+        (@setting SubcommandRequiredElseHelp)
+        (@arg url: -U --url +takes_value +global "REST API endpoint, defaults to http://localhost:8008")
+        (@arg key: -k --key +takes_value +global "signing key file, defaults to ~/.sawtooth/keys/$USER.priv")
+        (@subcommand keygen =>
+            (about: "generate a signing key in ~/.sawtooth/keys")
+            (@arg key_name: "name of the key, defaults to $USER")
+            (@arg force: --force "overwrite an existing key"))
+        (@subcommand balance =>
+            (about: "show the DGC balance of an account")
+            (@arg pubkey: "public key of the account, defaults to the signing key")
+            (@arg currency: --currency +takes_value "also show the credit in a currency"))
+        (@subcommand transfer =>
+            (about: "transfer DGC to another account")
+            (@arg beneficiary: +required "public key of the beneficiary")
+            (@arg amount: +required "DGC to transfer"))
+        (@subcommand apply_credit =>
+            (name: "apply-credit")
+            (about: "apply for credit in a currency")
+            (@arg currency: +required "currency of the credit")
+            (@arg amount: +required "credit amount"))
+        (@subcommand sell =>
+            (about: "sell DGC for a currency")
+            (@arg currency: +required "currency to receive")
+            (@arg amount: +required "DGC to sell")
+            (@arg price: --price +takes_value "limit price in currency per DGC; a market order when not given")
+            (@arg expected: --expected +takes_value "minimum currency to receive for the whole amount")
+            (@arg time_in_force: --("time-in-force") +takes_value possible_value[gtc ioc fok] "defaults to gtc")
+            (@arg expiry: --expiry +takes_value "Unix timestamp at which a resting order expires")
+            (@arg beneficiary: --beneficiary +takes_value "public key receiving the currency"))
+        (@subcommand buy =>
+            (about: "buy DGC with a currency")
+            (@arg currency: +required "currency to pay with")
+            (@arg amount: +required "DGC to buy")
+            (@arg price: --price +takes_value "limit price in currency per DGC; a market order when not given")
+            (@arg expected: --expected +takes_value "maximum currency to pay for the whole amount")
+            (@arg time_in_force: --("time-in-force") +takes_value possible_value[gtc ioc fok] "defaults to gtc")
+            (@arg expiry: --expiry +takes_value "Unix timestamp at which a resting order expires")
+            (@arg beneficiary: --beneficiary +takes_value "public key receiving the DGC"))
+        (@subcommand orders =>
+            (about: "list the resting orders of a currency")
+            (@arg currency: +required "currency of the book")
+            (@arg side: --side +takes_value possible_value[sell buy] "only one side of the book"))
+        (@subcommand cancel =>
+            (about: "cancel a resting order")
+            (@arg currency: +required "currency of the order")
+            (@arg side: +required possible_value[sell buy] "side of the order")
+            (@arg order_id: +required "id of the order")))
+        .get_matches();
+
+    if let Err(err) = run(&matches) {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run(matches: &ArgMatches) -> Result<(), CliError> {
+    let url = matches.value_of("url").unwrap_or(DEFAULT_URL).trim_end_matches('/');
+    match matches.subcommand() {
+        ("keygen", Some(args)) => {
+            let public_key = key::generate_key(args.value_of("key_name"), args.is_present("force"))?;
+            println!("{}", public_key);
+            Ok(())
+        }
+        ("balance", Some(args)) => do_balance(url, matches.value_of("key"), args),
+        ("transfer", Some(args)) => {
+            let mut action = payload::TransferDGCoinAction::new();
+            action.set_customer_pubkey(signer_public_key(matches.value_of("key"))?);
+            action.set_beneficiary_pubkey(args.value_of("beneficiary").unwrap_or("").to_string());
+            action.set_transfer_amount(parse_u32(args, "amount")?);
+            submit(url, matches.value_of("key"), Action::TransferDGCoin(action))
+        }
+        ("apply-credit", Some(args)) => {
+            let mut action = payload::ApplyCreditAction::new();
+            action.set_customer_pubkey(signer_public_key(matches.value_of("key"))?);
+            action.set_currency(args.value_of("currency").unwrap_or("").to_string());
+            action.set_credit_amount(parse_u32(args, "amount")?);
+            submit(url, matches.value_of("key"), Action::ApplyCredit(action))
+        }
+        ("sell", Some(args)) => {
+            let customer_pubkey = signer_public_key(matches.value_of("key"))?;
+            let mut action = payload::SellDGCoinAction::new();
+            action.set_beneficiary_pubkey(args.value_of("beneficiary").unwrap_or(&customer_pubkey).to_string());
+            action.set_customer_pubkey(customer_pubkey);
+            action.set_currency(args.value_of("currency").unwrap_or("").to_string());
+            action.set_sell_amount(parse_u32(args, "amount")?);
+            action.set_expected_sell_currency_amount(parse_optional_u32(args, "expected")?);
+            action.set_price(parse_optional_u32(args, "price")?);
+            action.set_order_type(order_type(args));
+            action.set_time_in_force(time_in_force(args));
+            action.set_expiry(parse_optional_u64(args, "expiry")?);
+            submit(url, matches.value_of("key"), Action::SellDGCoin(action))
+        }
+        ("buy", Some(args)) => {
+            let customer_pubkey = signer_public_key(matches.value_of("key"))?;
+            let mut action = payload::BuyDGCoinAction::new();
+            action.set_beneficiary_pubkey(args.value_of("beneficiary").unwrap_or(&customer_pubkey).to_string());
+            action.set_customer_pubkey(customer_pubkey);
+            action.set_currency(args.value_of("currency").unwrap_or("").to_string());
+            action.set_buy_amount(parse_u32(args, "amount")?);
+            action.set_expected_buy_currency_amount(parse_optional_u32(args, "expected")?);
+            action.set_price(parse_optional_u32(args, "price")?);
+            action.set_order_type(order_type(args));
+            action.set_time_in_force(time_in_force(args));
+            action.set_expiry(parse_optional_u64(args, "expiry")?);
+            submit(url, matches.value_of("key"), Action::BuyDGCoin(action))
+        }
+        ("orders", Some(args)) => do_orders(url, args),
+        ("cancel", Some(args)) => {
+            let mut action = payload::CancelOrderAction::new();
+            action.set_currency(args.value_of("currency").unwrap_or("").to_string());
+            action.set_side(order_side(args.value_of("side")));
+            action.set_order_id(args.value_of("order_id").unwrap_or("").to_string());
+            submit(url, matches.value_of("key"), Action::CancelOrder(action))
+        }
+        _ => Err(CliError::UserError(String::from("Unknown subcommand"))),
+    }
+}
+
+fn do_balance(url: &str, key: Option<&str>, args: &ArgMatches) -> Result<(), CliError> {
+    let pubkey = match args.value_of("pubkey") {
+        Some(pubkey) => pubkey.to_string(),
+        None => signer_public_key(key)?,
+    };
+    let client = QueryClient::new(url);
+    let account = match client.get_account(&pubkey)? {
+        Some(account) => account,
+        None => return Err(CliError::UserError(format!("No account for {}", pubkey))),
+    };
+    println!("balance: {}", account.get_balance());
+    if account.get_frozen() {
+        println!("frozen");
+    }
+    if let Some(currency) = args.value_of("currency") {
+        println!("{} credit: {}", currency, client.get_credit(&pubkey, currency)?);
+    }
+    Ok(())
+}
+
+fn do_orders(url: &str, args: &ArgMatches) -> Result<(), CliError> {
+    let currency = args.value_of("currency").unwrap_or("");
+    let sides = match args.value_of("side") {
+        Some(side) => vec![order_side(Some(side))],
+        None => vec![payload::OrderSide::SELL, payload::OrderSide::BUY],
+    };
+    let client = QueryClient::new(url);
+    for side in sides {
+        println!("{:?}", side);
+        for level in client.get_order_book(currency, side)? {
+            for order in level.orders {
+                println!(
+                    "  {} price {} quantity {} expiry {}",
+                    order.get_order_id(),
+                    order.get_price(),
+                    order.get_quantity(),
+                    order.get_expiry()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn submit(url: &str, key: Option<&str>, action: Action) -> Result<(), CliError> {
+    let private_key = key::load_signing_key(key)?;
+    let batch_list_bytes = TransactionBuilder::new(action).build_batch_list(&private_key)?;
+    let link = rest::submit_batch_list(url, batch_list_bytes)?;
+    println!("{}", link);
+    Ok(())
+}
+
+fn signer_public_key(key: Option<&str>) -> Result<String, CliError> {
+    let private_key = key::load_signing_key(key)?;
+    Ok(Secp256k1Context::new().get_public_key(&private_key)?.as_hex())
+}
+
+fn order_side(side: Option<&str>) -> payload::OrderSide {
+    match side {
+        Some("buy") => payload::OrderSide::BUY,
+        _ => payload::OrderSide::SELL,
+    }
+}
+
+fn order_type(args: &ArgMatches) -> payload::OrderType {
+    if args.is_present("price") {
+        payload::OrderType::LIMIT
+    } else {
+        payload::OrderType::MARKET
+    }
+}
+
+fn time_in_force(args: &ArgMatches) -> payload::TimeInForce {
+    match args.value_of("time_in_force") {
+        Some("ioc") => payload::TimeInForce::IOC,
+        Some("fok") => payload::TimeInForce::FOK,
+        _ => payload::TimeInForce::GTC,
+    }
+}
+
+fn parse_u32(args: &ArgMatches, name: &str) -> Result<u32, CliError> {
+    let value = args.value_of(name).unwrap_or("");
+    value
+        .parse()
+        .map_err(|_| CliError::UserError(format!("{} must be a whole number, got {}", name, value)))
+}
+
+fn parse_optional_u32(args: &ArgMatches, name: &str) -> Result<u32, CliError> {
+    match args.value_of(name) {
+        Some(_) => parse_u32(args, name),
+        None => Ok(0),
+    }
+}
+
+fn parse_optional_u64(args: &ArgMatches, name: &str) -> Result<u64, CliError> {
+    match args.value_of(name) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| CliError::UserError(format!("{} must be a whole number, got {}", name, value))),
+        None => Ok(0),
+    }
+}
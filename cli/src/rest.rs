@@ -0,0 +1,26 @@
+// Copyright (c) The dgc.network
+// SPDX-License-Identifier: Apache-2.0
+
+use reqwest;
+use reqwest::header::CONTENT_TYPE;
+use serde_json;
+
+use error::CliError;
+
+//Submits serialized BatchList bytes and returns the batch status link
+pub fn submit_batch_list(url: &str, batch_list_bytes: Vec<u8>) -> Result<String, CliError> {
+    let client = reqwest::Client::new();
+    let mut response = client
+        .post(&format!("{}/batches", url))
+        .header(CONTENT_TYPE, "application/octet-stream")
+        .body(batch_list_bytes)
+        .send()?;
+    let body: serde_json::Value = response.json()?;
+    if !response.status().is_success() {
+        return Err(CliError::RestApiError(body.to_string()));
+    }
+    match body["link"].as_str() {
+        Some(link) => Ok(link.to_string()),
+        None => Err(CliError::RestApiError(body.to_string())),
+    }
+}
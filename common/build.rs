@@ -0,0 +1,15 @@
+// Copyright (c) The dgc.network
+// SPDX-License-Identifier: Apache-2.0
+
+extern crate protoc_rust;
+
+use protoc_rust::Customize;
+
+fn main() {
+    protoc_rust::run(protoc_rust::Args {
+        out_dir: "src/protos",
+        input: &["protos/payload.proto", "protos/state.proto", "protos/receipt.proto"],
+        includes: &["protos"],
+        customize: Customize::default(),
+    }).expect("Error generating rust files from dgc-core protos");
+}
@@ -0,0 +1,97 @@
+// Copyright (c) The dgc.network
+// SPDX-License-Identifier: Apache-2.0
+
+//Reads dgc-core state from the REST API of a validator
+
+use base64;
+use reqwest;
+use reqwest::StatusCode;
+use serde_json;
+
+use error::QueryError;
+use protos::payload::OrderSide;
+use protos::state::{Account, ExchangeRate, Order};
+use query;
+use query::PriceLevel;
+use state;
+
+pub const DEFAULT_URL: &str = "http://localhost:8008";
+
+pub struct QueryClient {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl QueryClient {
+    //Client of the REST API at the url, e.g. http://localhost:8008
+    pub fn new(url: &str) -> QueryClient {
+        QueryClient {
+            url: url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    //Raw bytes at a state address, None when nothing is stored there
+    pub fn get_state(&self, address: &str) -> Result<Option<Vec<u8>>, QueryError> {
+        let mut response = self.client.get(&format!("{}/state/{}", self.url, address)).send()?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let body: serde_json::Value = response.json()?;
+        if !response.status().is_success() {
+            return Err(QueryError::RestApiError(body.to_string()));
+        }
+        match body["data"].as_str() {
+            Some(data) => base64::decode(data)
+                .map(Some)
+                .map_err(|err| QueryError::RestApiError(format!("Invalid state data: {}", err))),
+            None => Err(QueryError::RestApiError(body.to_string())),
+        }
+    }
+
+    pub fn get_account(&self, pubkey: &str) -> Result<Option<Account>, QueryError> {
+        match self.get_state(&state::make_balance_state_address(pubkey))? {
+            Some(packed) => query::decode_account(&packed).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    //Credit of an account in a currency, 0 when it has none
+    pub fn get_credit(&self, pubkey: &str, currency: &str) -> Result<u32, QueryError> {
+        match self.get_state(&state::make_credit_state_address(pubkey, currency))? {
+            Some(packed) => query::decode_credit(&packed),
+            None => Ok(0),
+        }
+    }
+
+    pub fn get_exchange_rate(&self, currency: &str) -> Result<Option<ExchangeRate>, QueryError> {
+        match self.get_state(&state::make_exchange_state_address(currency))? {
+            Some(packed) => query::decode_exchange_rate(&packed).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_order(&self, currency: &str, side: OrderSide, order_id: &str) -> Result<Option<Order>, QueryError> {
+        let address = match side {
+            OrderSide::SELL => state::make_sell_state_address(currency, order_id),
+            OrderSide::BUY => state::make_buy_state_address(currency, order_id),
+        };
+        match self.get_state(&address)? {
+            Some(packed) => query::decode_order(&packed).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    //Resting orders of one side of a currency's book, best price first
+    pub fn get_order_book(&self, currency: &str, side: OrderSide) -> Result<Vec<PriceLevel>, QueryError> {
+        let address = match side {
+            OrderSide::SELL => state::make_sell_book_state_address(currency),
+            OrderSide::BUY => state::make_buy_book_state_address(currency),
+        };
+        let book = match self.get_state(&address)? {
+            Some(packed) => query::decode_order_book(&packed)?,
+            None => return Ok(Vec::new()),
+        };
+        query::resolve_order_book(&book, |order_id| self.get_order(currency, side, order_id))
+    }
+}
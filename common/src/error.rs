@@ -0,0 +1,98 @@
+// Copyright (c) The dgc.network
+// SPDX-License-Identifier: Apache-2.0
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+
+use protobuf::ProtobufError;
+#[cfg(feature = "client")]
+use reqwest;
+use sawtooth_sdk::signing;
+
+#[derive(Debug)]
+pub enum TransactionError {
+    SigningError(signing::Error),
+    ProtobufError(ProtobufError),
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TransactionError::SigningError(ref err) => write!(f, "SigningError: {}", err),
+            TransactionError::ProtobufError(ref err) => write!(f, "ProtobufError: {}", err),
+        }
+    }
+}
+
+impl StdError for TransactionError {
+    fn description(&self) -> &str {
+        match *self {
+            TransactionError::SigningError(ref err) => err.description(),
+            TransactionError::ProtobufError(ref err) => err.description(),
+        }
+    }
+}
+
+impl From<signing::Error> for TransactionError {
+    fn from(e: signing::Error) -> Self {
+        TransactionError::SigningError(e)
+    }
+}
+
+impl From<ProtobufError> for TransactionError {
+    fn from(e: ProtobufError) -> Self {
+        TransactionError::ProtobufError(e)
+    }
+}
+
+#[derive(Debug)]
+pub enum QueryError {
+    //State bytes that are not what is stored at the address
+    InvalidState(String),
+    IoError(io::Error),
+    #[cfg(feature = "client")]
+    HttpError(reqwest::Error),
+    //The REST API answered with something other than what was asked for
+    #[cfg(feature = "client")]
+    RestApiError(String),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            QueryError::InvalidState(ref msg) => write!(f, "InvalidState: {}", msg),
+            QueryError::IoError(ref err) => write!(f, "IoError: {}", err),
+            #[cfg(feature = "client")]
+            QueryError::HttpError(ref err) => write!(f, "HttpError: {}", err),
+            #[cfg(feature = "client")]
+            QueryError::RestApiError(ref msg) => write!(f, "RestApiError: {}", msg),
+        }
+    }
+}
+
+impl StdError for QueryError {
+    fn description(&self) -> &str {
+        match *self {
+            QueryError::InvalidState(ref msg) => msg,
+            QueryError::IoError(ref err) => err.description(),
+            #[cfg(feature = "client")]
+            QueryError::HttpError(ref err) => err.description(),
+            #[cfg(feature = "client")]
+            QueryError::RestApiError(ref msg) => msg,
+        }
+    }
+}
+
+impl From<io::Error> for QueryError {
+    fn from(e: io::Error) -> Self {
+        QueryError::IoError(e)
+    }
+}
+
+#[cfg(feature = "client")]
+impl From<reqwest::Error> for QueryError {
+    fn from(e: reqwest::Error) -> Self {
+        QueryError::HttpError(e)
+    }
+}
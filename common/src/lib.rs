@@ -0,0 +1,25 @@
+// Copyright (c) The dgc.network
+// SPDX-License-Identifier: Apache-2.0
+
+//Payload decoding and state addressing of the dgc-core transaction family,
+//shared by the transaction processor and the Rust clients so that both
+//agree on where every piece of state lives.
+
+#[cfg(feature = "client")]
+extern crate base64;
+extern crate crypto;
+extern crate protobuf;
+#[cfg(feature = "client")]
+extern crate reqwest;
+extern crate sawtooth_sdk;
+#[cfg(feature = "client")]
+extern crate serde_json;
+
+#[cfg(feature = "client")]
+pub mod client;
+pub mod error;
+pub mod payload;
+pub mod protos;
+pub mod query;
+pub mod state;
+pub mod transaction;
@@ -0,0 +1,744 @@
+// Copyright (c) The dgc.network
+// SPDX-License-Identifier: Apache-2.0
+
+use protobuf;
+
+use std::str;
+
+use sawtooth_sdk::processor::handler::ApplyError;
+
+use protos::payload;
+
+//Version 1.0 payloads are limited to the original actions; 2.0 carries all
+pub const FAMILY_VERSION_1: &str = "1.0";
+pub const FAMILY_VERSION_2: &str = "2.0";
+
+//Action names of the comma separated payloads sent by legacy 1.0 clients
+const LEGACY_ACTIONS: [&str; 11] = [
+    "deposit", "withdraw", "transfer", "transferDGC", "dgcBalance", "applyCredit", "apply",
+    "sellDGC", "sell", "buyDGC", "buy",
+];
+//Legacy payloads without a timestamp are applied at this one
+const LEGACY_TIMESTAMP: u64 = 1;
+//Legacy actions that check expiries, windows or price freshness, with the
+//number of items of their payloads when they end with the timestamp they
+//were submitted at
+const LEGACY_TIMED_ACTIONS: [(&str, usize); 7] = [
+    ("withdraw", 3), ("transfer", 4), ("transferDGC", 4), ("sellDGC", 5), ("sell", 5), ("buyDGC", 5), ("buy", 5),
+];
+
+#[derive(Debug, Clone)]
+pub enum Action {
+    ApplyCredit(payload::ApplyCreditAction),
+    TransferDGCoin(payload::TransferDGCoinAction),
+    WithdrawDGCoin(payload::WithdrawDGCoinAction),
+    DGCBalance(payload::DGCBalanceAction),
+    SellDGCoin(payload::SellDGCoinAction),
+    BuyDGCoin(payload::BuyDGCoinAction),
+    LockWithHash(payload::LockWithHashAction),
+    ClaimWithPreimage(payload::ClaimWithPreimageAction),
+    RefundAfterTimeout(payload::RefundAfterTimeoutAction),
+    CreateMultisig(payload::CreateMultisigAction),
+    ProposeMultisigTransfer(payload::ProposeMultisigTransferAction),
+    ApproveMultisigTransfer(payload::ApproveMultisigTransferAction),
+    CreateVestingGrant(payload::CreateVestingGrantAction),
+    ClaimVested(payload::ClaimVestedAction),
+    CreateStandingOrder(payload::CreateStandingOrderAction),
+    ExecuteStandingOrder(payload::ExecuteStandingOrderAction),
+    CancelStandingOrder(payload::CancelStandingOrderAction),
+    FreezeAccount(payload::FreezeAccountAction),
+    UnfreezeAccount(payload::UnfreezeAccountAction),
+    AttestKyc(payload::AttestKycAction),
+    SetSpendingLimits(payload::SetSpendingLimitsAction),
+    CancelOrder(payload::CancelOrderAction),
+    PurgeExpiredOrders(payload::PurgeExpiredOrdersAction),
+    AddLiquidity(payload::AddLiquidityAction),
+    RemoveLiquidity(payload::RemoveLiquidityAction),
+    Swap(payload::SwapAction),
+    UpdateExchangeRate(payload::UpdateExchangeRateAction),
+    ResumeTrading(payload::ResumeTradingAction),
+    Pause(payload::PauseAction),
+    Unpause(payload::UnpauseAction),
+    MigrateAccounts(payload::MigrateAccountsAction),
+}
+
+impl Action {
+    //Admin actions are still accepted while the processor is paused
+    pub fn is_admin(&self) -> bool {
+        match *self {
+            Action::Pause(_)
+            | Action::Unpause(_)
+            | Action::MigrateAccounts(_)
+            | Action::ResumeTrading(_)
+            | Action::FreezeAccount(_)
+            | Action::UnfreezeAccount(_)
+            | Action::SetSpendingLimits(_) => true,
+            _ => false,
+        }
+    }
+
+    //Protobuf payload carrying the action, as sent by clients
+    pub fn to_payload(&self, timestamp: u64) -> payload::DGCPayload {
+        let mut payload = payload::DGCPayload::new();
+        match *self {
+            Action::ApplyCredit(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::APPLY_CREDIT);
+                payload.set_apply_credit(action.clone());
+            }
+            Action::TransferDGCoin(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::TRANSFER_DG_COIN);
+                payload.set_transfer_dg_coin(action.clone());
+            }
+            Action::WithdrawDGCoin(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::WITHDRAW_DG_COIN);
+                payload.set_withdraw_dg_coin(action.clone());
+            }
+            Action::DGCBalance(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::DGC_BALANCE);
+                payload.set_dgc_balance(action.clone());
+            }
+            Action::SellDGCoin(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::SELL_DG_COIN);
+                payload.set_sell_dg_coin(action.clone());
+            }
+            Action::BuyDGCoin(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::BUY_DG_COIN);
+                payload.set_buy_dg_coin(action.clone());
+            }
+            Action::LockWithHash(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::LOCK_WITH_HASH);
+                payload.set_lock_with_hash(action.clone());
+            }
+            Action::ClaimWithPreimage(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::CLAIM_WITH_PREIMAGE);
+                payload.set_claim_with_preimage(action.clone());
+            }
+            Action::RefundAfterTimeout(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::REFUND_AFTER_TIMEOUT);
+                payload.set_refund_after_timeout(action.clone());
+            }
+            Action::CreateMultisig(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::CREATE_MULTISIG);
+                payload.set_create_multisig(action.clone());
+            }
+            Action::ProposeMultisigTransfer(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::PROPOSE_MULTISIG_TRANSFER);
+                payload.set_propose_multisig_transfer(action.clone());
+            }
+            Action::ApproveMultisigTransfer(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::APPROVE_MULTISIG_TRANSFER);
+                payload.set_approve_multisig_transfer(action.clone());
+            }
+            Action::CreateVestingGrant(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::CREATE_VESTING_GRANT);
+                payload.set_create_vesting_grant(action.clone());
+            }
+            Action::ClaimVested(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::CLAIM_VESTED);
+                payload.set_claim_vested(action.clone());
+            }
+            Action::CreateStandingOrder(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::CREATE_STANDING_ORDER);
+                payload.set_create_standing_order(action.clone());
+            }
+            Action::ExecuteStandingOrder(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::EXECUTE_STANDING_ORDER);
+                payload.set_execute_standing_order(action.clone());
+            }
+            Action::CancelStandingOrder(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::CANCEL_STANDING_ORDER);
+                payload.set_cancel_standing_order(action.clone());
+            }
+            Action::FreezeAccount(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::FREEZE_ACCOUNT);
+                payload.set_freeze_account(action.clone());
+            }
+            Action::UnfreezeAccount(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::UNFREEZE_ACCOUNT);
+                payload.set_unfreeze_account(action.clone());
+            }
+            Action::AttestKyc(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::ATTEST_KYC);
+                payload.set_attest_kyc(action.clone());
+            }
+            Action::SetSpendingLimits(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::SET_SPENDING_LIMITS);
+                payload.set_set_spending_limits(action.clone());
+            }
+            Action::CancelOrder(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::CANCEL_ORDER);
+                payload.set_cancel_order(action.clone());
+            }
+            Action::PurgeExpiredOrders(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::PURGE_EXPIRED_ORDERS);
+                payload.set_purge_expired_orders(action.clone());
+            }
+            Action::AddLiquidity(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::ADD_LIQUIDITY);
+                payload.set_add_liquidity(action.clone());
+            }
+            Action::RemoveLiquidity(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::REMOVE_LIQUIDITY);
+                payload.set_remove_liquidity(action.clone());
+            }
+            Action::Swap(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::SWAP);
+                payload.set_swap(action.clone());
+            }
+            Action::UpdateExchangeRate(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::UPDATE_EXCHANGE_RATE);
+                payload.set_update_exchange_rate(action.clone());
+            }
+            Action::ResumeTrading(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::RESUME_TRADING);
+                payload.set_resume_trading(action.clone());
+            }
+            Action::Pause(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::PAUSE);
+                payload.set_pause(action.clone());
+            }
+            Action::Unpause(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::UNPAUSE);
+                payload.set_unpause(action.clone());
+            }
+            Action::MigrateAccounts(ref action) => {
+                payload.set_action(payload::DGCPayload_Action::MIGRATE_ACCOUNTS);
+                payload.set_migrate_accounts(action.clone());
+            }
+        }
+        payload.set_timestamp(timestamp);
+        payload
+    }
+}
+
+pub struct DGCPayload {
+    action: Action,
+    action_type: payload::DGCPayload_Action,
+    timestamp: u64,
+}
+/*
+pub enum Action {
+    Deposit,
+    Withdraw,
+    Balance,
+    Transfer,
+    BalanceDGC,
+    CreditDGC,
+    ExchangeDGC,
+    ApplyCredit,
+    TransferDGCoin,
+    SellDGCoin,
+    BuyDGCoin,
+}
+
+pub struct DGCPayload {
+    action: Action,    
+    value: u32,
+    beneficiary_pubkey: Option<String>,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Action::Deposit => "Action::Deposit",
+                Action::Withdraw => "Action::Withdraw",
+                Action::Balance => "Action::Balance",
+                Action::Transfer => "Action::Transfer",
+                Action::BalanceDGC => "Action::BalanceDGC",
+                Action::ExchangeDGC => "Action::ExchangeDGC",
+                Action::CreditDGC => "Action::CreditDGC",
+                Action::ApplyCredit => "Action::ApplyCredit",
+                Action::TransferDGCoin => "Action::TransferDGCoin",
+                Action::SellDGCoin => "Action::SellDGCoin",
+                Action::BuyDGCoin => "Action::BuyDGCoin",
+            }
+        )
+    }
+}
+*/
+impl DGCPayload {
+
+    pub fn new(payload: &[u8], family_version: &str, signer: &str) -> Result<Option<DGCPayload>, ApplyError> {
+        if family_version == FAMILY_VERSION_1 && is_legacy(payload) {
+            return DGCPayload::from_legacy(payload, signer)
+        }
+
+        let payload: payload::DGCPayload = match protobuf::parse_from_bytes(payload) {
+            Ok(payload) => payload,
+            Err(_) => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Cannot deserialize payload",
+                )))
+            }
+        };
+
+        let dgc_core_action = payload.get_action();
+        let action = match dgc_core_action {
+            payload::DGCPayload_Action::APPLY_CREDIT => {
+                Action::ApplyCredit(payload.get_apply_credit().clone())
+            }
+            payload::DGCPayload_Action::TRANSFER_DG_COIN => {
+                Action::TransferDGCoin(payload.get_transfer_dg_coin().clone())
+            }
+            payload::DGCPayload_Action::WITHDRAW_DG_COIN => {
+                Action::WithdrawDGCoin(payload.get_withdraw_dg_coin().clone())
+            }
+            payload::DGCPayload_Action::DGC_BALANCE => {
+                Action::DGCBalance(payload.get_dgc_balance().clone())
+            }
+            payload::DGCPayload_Action::SELL_DG_COIN => {
+                Action::SellDGCoin(payload.get_sell_dg_coin().clone())
+            }
+            payload::DGCPayload_Action::BUY_DG_COIN => {
+                Action::BuyDGCoin(payload.get_buy_dg_coin().clone())
+            }
+            payload::DGCPayload_Action::LOCK_WITH_HASH => {
+                Action::LockWithHash(payload.get_lock_with_hash().clone())
+            }
+            payload::DGCPayload_Action::CLAIM_WITH_PREIMAGE => {
+                Action::ClaimWithPreimage(payload.get_claim_with_preimage().clone())
+            }
+            payload::DGCPayload_Action::REFUND_AFTER_TIMEOUT => {
+                Action::RefundAfterTimeout(payload.get_refund_after_timeout().clone())
+            }
+            payload::DGCPayload_Action::CREATE_MULTISIG => {
+                Action::CreateMultisig(payload.get_create_multisig().clone())
+            }
+            payload::DGCPayload_Action::PROPOSE_MULTISIG_TRANSFER => {
+                Action::ProposeMultisigTransfer(payload.get_propose_multisig_transfer().clone())
+            }
+            payload::DGCPayload_Action::APPROVE_MULTISIG_TRANSFER => {
+                Action::ApproveMultisigTransfer(payload.get_approve_multisig_transfer().clone())
+            }
+            payload::DGCPayload_Action::CREATE_VESTING_GRANT => {
+                Action::CreateVestingGrant(payload.get_create_vesting_grant().clone())
+            }
+            payload::DGCPayload_Action::CLAIM_VESTED => {
+                Action::ClaimVested(payload.get_claim_vested().clone())
+            }
+            payload::DGCPayload_Action::CREATE_STANDING_ORDER => {
+                Action::CreateStandingOrder(payload.get_create_standing_order().clone())
+            }
+            payload::DGCPayload_Action::EXECUTE_STANDING_ORDER => {
+                Action::ExecuteStandingOrder(payload.get_execute_standing_order().clone())
+            }
+            payload::DGCPayload_Action::CANCEL_STANDING_ORDER => {
+                Action::CancelStandingOrder(payload.get_cancel_standing_order().clone())
+            }
+            payload::DGCPayload_Action::FREEZE_ACCOUNT => {
+                Action::FreezeAccount(payload.get_freeze_account().clone())
+            }
+            payload::DGCPayload_Action::UNFREEZE_ACCOUNT => {
+                Action::UnfreezeAccount(payload.get_unfreeze_account().clone())
+            }
+            payload::DGCPayload_Action::ATTEST_KYC => {
+                Action::AttestKyc(payload.get_attest_kyc().clone())
+            }
+            payload::DGCPayload_Action::SET_SPENDING_LIMITS => {
+                Action::SetSpendingLimits(payload.get_set_spending_limits().clone())
+            }
+            payload::DGCPayload_Action::CANCEL_ORDER => {
+                Action::CancelOrder(payload.get_cancel_order().clone())
+            }
+            payload::DGCPayload_Action::PURGE_EXPIRED_ORDERS => {
+                Action::PurgeExpiredOrders(payload.get_purge_expired_orders().clone())
+            }
+            payload::DGCPayload_Action::ADD_LIQUIDITY => {
+                Action::AddLiquidity(payload.get_add_liquidity().clone())
+            }
+            payload::DGCPayload_Action::REMOVE_LIQUIDITY => {
+                Action::RemoveLiquidity(payload.get_remove_liquidity().clone())
+            }
+            payload::DGCPayload_Action::SWAP => {
+                Action::Swap(payload.get_swap().clone())
+            }
+            payload::DGCPayload_Action::UPDATE_EXCHANGE_RATE => {
+                Action::UpdateExchangeRate(payload.get_update_exchange_rate().clone())
+            }
+            payload::DGCPayload_Action::RESUME_TRADING => {
+                Action::ResumeTrading(payload.get_resume_trading().clone())
+            }
+            payload::DGCPayload_Action::PAUSE => {
+                Action::Pause(payload.get_pause().clone())
+            }
+            payload::DGCPayload_Action::UNPAUSE => {
+                Action::Unpause(payload.get_unpause().clone())
+            }
+            payload::DGCPayload_Action::MIGRATE_ACCOUNTS => {
+                Action::MigrateAccounts(payload.get_migrate_accounts().clone())
+            }
+            payload::DGCPayload_Action::ACTION_UNSET => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action is not set",
+                )))
+            }
+        };
+
+        let action = match family_version {
+            FAMILY_VERSION_1 => to_version_1(action)?,
+            FAMILY_VERSION_2 => action,
+            _ => {
+                return Err(ApplyError::InvalidTransaction(format!(
+                    "Unsupported family version {}",
+                    family_version
+                )))
+            }
+        };
+
+        let timestamp = match payload.get_timestamp() {
+            0 => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Timestamp is not set",
+                )))
+            }
+            x => x,
+        };
+
+        Ok(Some(DGCPayload {
+            action: action,
+            action_type: payload.get_action(),
+            timestamp: timestamp,
+        }))
+    }
+
+    pub fn get_action(&self) -> Action {
+        self.action.clone()
+    }
+
+    pub fn get_action_type(&self) -> payload::DGCPayload_Action {
+        self.action_type
+    }
+
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+    //Legacy clients send comma separated "action,value[,beneficiary or
+    //currency[,expected currency amount]][,timestamp]" strings on behalf of
+    //the signer. LEGACY_TIMED_ACTIONS may carry the timestamp, with the
+    //expected currency amount of sells and buys required before it; payloads
+    //without one are applied at LEGACY_TIMESTAMP as 1.0 clients send them.
+    fn from_legacy(payload_data: &[u8], signer: &str) -> Result<Option<DGCPayload>, ApplyError> {
+
+        let payload_string = match str::from_utf8(&payload_data) {
+            Ok(s) => s,
+            Err(_) => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Invalid payload serialization",
+                )))
+            }
+        };
+
+        //dgc payload is constructed as comma separated items
+        let mut items: Vec<&str> = payload_string.split(",").collect();
+
+        let timestamp = match LEGACY_TIMED_ACTIONS.iter().find(|&&(action, _)| action == items[0]) {
+            Some(&(_, len)) if items.len() == len => match items.pop().and_then(|v| v.parse().ok()) {
+                Some(v) if v != 0 => v,
+                _ => {
+                    return Err(ApplyError::InvalidTransaction(format!(
+                        "Legacy action {} has an invalid timestamp",
+                        items[0]
+                    )))
+                }
+            },
+            _ => LEGACY_TIMESTAMP,
+        };
+
+        if items.len() < 2 {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Payload must have at least 1 comma",
+            )));
+        }
+
+        if items.len() > 4 {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Payload must have at most 3 commas",
+            )));
+        }
+
+        let value: u32 = match items[1].parse() {
+            Ok(num) => num,
+            Err(_) => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Missing integer value",
+                )))
+            }
+        };
+
+        //Beneficiary for transfers, currency for credit, sells and buys
+        let target = match items.get(2) {
+            Some(v) if v.is_empty() => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Beneficiary cannot be empty ",
+                )))
+            }
+            Some(v) => Some(v.to_string()),
+            None => None,
+        };
+
+        let expected_currency_amount: u32 = match items.get(3) {
+            Some(v) => match v.parse() {
+                Ok(num) => num,
+                Err(_) => {
+                    return Err(ApplyError::InvalidTransaction(String::from(
+                        "Invalid expected currency amount",
+                    )))
+                }
+            },
+            None => 0,
+        };
+
+        let (action, action_type) = match items[0] {
+            //Deposits become DGC credit of the signer
+            "deposit" => {
+                let mut apply_credit = payload::ApplyCreditAction::new();
+                apply_credit.set_customer_pubkey(signer.to_string());
+                apply_credit.set_currency(String::from("DGC"));
+                apply_credit.set_credit_amount(value);
+                (Action::ApplyCredit(apply_credit), payload::DGCPayload_Action::APPLY_CREDIT)
+            }
+            "withdraw" => {
+                let mut withdraw_dg_coin = payload::WithdrawDGCoinAction::new();
+                withdraw_dg_coin.set_customer_pubkey(signer.to_string());
+                withdraw_dg_coin.set_withdraw_amount(value);
+                (Action::WithdrawDGCoin(withdraw_dg_coin), payload::DGCPayload_Action::WITHDRAW_DG_COIN)
+            }
+            //The value of a balance request is ignored
+            "dgcBalance" => {
+                let mut dgc_balance = payload::DGCBalanceAction::new();
+                dgc_balance.set_customer_pubkey(signer.to_string());
+                (Action::DGCBalance(dgc_balance), payload::DGCPayload_Action::DGC_BALANCE)
+            }
+            "applyCredit" | "apply" => {
+                let mut apply_credit = payload::ApplyCreditAction::new();
+                apply_credit.set_customer_pubkey(signer.to_string());
+                apply_credit.set_currency(required_item(target, "Action: ApplyCredit. currency doesn't exist.")?);
+                apply_credit.set_credit_amount(value);
+                (Action::ApplyCredit(apply_credit), payload::DGCPayload_Action::APPLY_CREDIT)
+            }
+            "transfer" | "transferDGC" => {
+                let mut transfer_dg_coin = payload::TransferDGCoinAction::new();
+                transfer_dg_coin.set_customer_pubkey(signer.to_string());
+                transfer_dg_coin.set_beneficiary_pubkey(required_item(target, "Action: Transfer. beneficiary account doesn't exist.")?);
+                transfer_dg_coin.set_transfer_amount(value);
+                (Action::TransferDGCoin(transfer_dg_coin), payload::DGCPayload_Action::TRANSFER_DG_COIN)
+            }
+            "sellDGC" | "sell" => {
+                let mut sell_dg_coin = payload::SellDGCoinAction::new();
+                sell_dg_coin.set_customer_pubkey(signer.to_string());
+                sell_dg_coin.set_beneficiary_pubkey(signer.to_string());
+                sell_dg_coin.set_currency(required_item(target, "Action: SellDGCoin. currency doesn't exist.")?);
+                sell_dg_coin.set_sell_amount(value);
+                sell_dg_coin.set_expected_sell_currency_amount(expected_currency_amount);
+                (Action::SellDGCoin(sell_dg_coin), payload::DGCPayload_Action::SELL_DG_COIN)
+            }
+            "buyDGC" | "buy" => {
+                let mut buy_dg_coin = payload::BuyDGCoinAction::new();
+                buy_dg_coin.set_customer_pubkey(signer.to_string());
+                buy_dg_coin.set_beneficiary_pubkey(signer.to_string());
+                buy_dg_coin.set_currency(required_item(target, "Action: BuyDGCoin. currency doesn't exist.")?);
+                buy_dg_coin.set_buy_amount(value);
+                buy_dg_coin.set_expected_buy_currency_amount(expected_currency_amount);
+                (Action::BuyDGCoin(buy_dg_coin), payload::DGCPayload_Action::BUY_DG_COIN)
+            }
+            _ => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Invalid Action",
+                )))
+            }
+        };
+
+        Ok(Some(DGCPayload {
+            action: to_version_1(action)?,
+            action_type: action_type,
+            timestamp: timestamp,
+        }))
+    }
+}
+
+//Version 1.0 sells and buys predate resting orders and settle immediately
+//against the book, bounded by the expected currency amount
+fn to_version_1(action: Action) -> Result<Action, ApplyError> {
+    match action {
+        Action::ApplyCredit(apply_credit) => Ok(Action::ApplyCredit(apply_credit)),
+        Action::TransferDGCoin(transfer_dg_coin) => Ok(Action::TransferDGCoin(transfer_dg_coin)),
+        Action::WithdrawDGCoin(withdraw_dg_coin) => Ok(Action::WithdrawDGCoin(withdraw_dg_coin)),
+        Action::DGCBalance(dgc_balance) => Ok(Action::DGCBalance(dgc_balance)),
+        Action::SellDGCoin(mut sell_dg_coin) => {
+            sell_dg_coin.set_order_type(payload::OrderType::MARKET);
+            sell_dg_coin.set_time_in_force(payload::TimeInForce::IOC);
+            sell_dg_coin.set_price(0);
+            sell_dg_coin.set_expiry(0);
+            Ok(Action::SellDGCoin(sell_dg_coin))
+        }
+        Action::BuyDGCoin(mut buy_dg_coin) => {
+            buy_dg_coin.set_order_type(payload::OrderType::MARKET);
+            buy_dg_coin.set_time_in_force(payload::TimeInForce::IOC);
+            buy_dg_coin.set_price(0);
+            buy_dg_coin.set_expiry(0);
+            Ok(Action::BuyDGCoin(buy_dg_coin))
+        }
+        _ => Err(ApplyError::InvalidTransaction(format!(
+            "Action requires family version {}",
+            FAMILY_VERSION_2
+        ))),
+    }
+}
+
+//Legacy payloads are UTF-8 and start with a legacy action name and a comma,
+//which no protobuf payload written with the action field first does
+fn is_legacy(payload: &[u8]) -> bool {
+    match str::from_utf8(payload) {
+        Ok(s) => match s.find(',') {
+            Some(i) => LEGACY_ACTIONS.contains(&&s[..i]),
+            None => false,
+        },
+        Err(_) => false,
+    }
+}
+
+fn required_item(item: Option<String>, message: &str) -> Result<String, ApplyError> {
+    match item {
+        Some(v) => Ok(v),
+        None => Err(ApplyError::InvalidTransaction(String::from(message))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use protobuf::Message;
+
+    const SIGNER: &str = "02a2a0b1e5a0a0c5e5a0a0b1e5a0a0c5e5a0a0b1e5a0a0c5e5a0a0b1e5a0a0c5e5";
+
+    fn decode_legacy(payload: &str) -> Result<DGCPayload, ApplyError> {
+        DGCPayload::new(payload.as_bytes(), FAMILY_VERSION_1, SIGNER).map(|payload| payload.unwrap())
+    }
+
+    #[test]
+    fn legacy_payloads_are_detected() {
+        assert!(is_legacy(b"transfer,10,bob,1600000000"));
+        assert!(is_legacy(b"dgcBalance,0"));
+        assert!(!is_legacy(b"transfer"));
+        assert!(!is_legacy(b"unknown,10"));
+        let payload = Action::DGCBalance(payload::DGCBalanceAction::new()).to_payload(1600000000);
+        assert!(!is_legacy(&payload.write_to_bytes().unwrap()));
+    }
+
+    #[test]
+    fn legacy_deposit_is_dgc_credit() {
+        let payload = decode_legacy("deposit,25").unwrap();
+        assert_eq!(payload.get_action_type(), payload::DGCPayload_Action::APPLY_CREDIT);
+        assert_eq!(payload.get_timestamp(), LEGACY_TIMESTAMP);
+        match payload.get_action() {
+            Action::ApplyCredit(action) => {
+                assert_eq!(action.get_customer_pubkey(), SIGNER);
+                assert_eq!(action.get_currency(), "DGC");
+                assert_eq!(action.get_credit_amount(), 25);
+            }
+            action => panic!("Unexpected action {:?}", action),
+        }
+    }
+
+    #[test]
+    fn legacy_balance_request() {
+        let payload = decode_legacy("dgcBalance,0").unwrap();
+        assert_eq!(payload.get_action_type(), payload::DGCPayload_Action::DGC_BALANCE);
+        match payload.get_action() {
+            Action::DGCBalance(action) => assert_eq!(action.get_customer_pubkey(), SIGNER),
+            action => panic!("Unexpected action {:?}", action),
+        }
+    }
+
+    #[test]
+    fn legacy_withdraw() {
+        let payload = decode_legacy("withdraw,7,1600000000").unwrap();
+        assert_eq!(payload.get_timestamp(), 1600000000);
+        match payload.get_action() {
+            Action::WithdrawDGCoin(action) => {
+                assert_eq!(action.get_customer_pubkey(), SIGNER);
+                assert_eq!(action.get_withdraw_amount(), 7);
+            }
+            action => panic!("Unexpected action {:?}", action),
+        }
+    }
+
+    #[test]
+    fn legacy_transfer() {
+        let payload = decode_legacy("transfer,10,bob,1600000000").unwrap();
+        assert_eq!(payload.get_timestamp(), 1600000000);
+        match payload.get_action() {
+            Action::TransferDGCoin(action) => {
+                assert_eq!(action.get_customer_pubkey(), SIGNER);
+                assert_eq!(action.get_beneficiary_pubkey(), "bob");
+                assert_eq!(action.get_transfer_amount(), 10);
+            }
+            action => panic!("Unexpected action {:?}", action),
+        }
+    }
+
+    #[test]
+    fn legacy_sell_settles_immediately() {
+        let payload = decode_legacy("sellDGC,10,USD,95,1600000000").unwrap();
+        assert_eq!(payload.get_timestamp(), 1600000000);
+        match payload.get_action() {
+            Action::SellDGCoin(action) => {
+                assert_eq!(action.get_customer_pubkey(), SIGNER);
+                assert_eq!(action.get_beneficiary_pubkey(), SIGNER);
+                assert_eq!(action.get_currency(), "USD");
+                assert_eq!(action.get_sell_amount(), 10);
+                assert_eq!(action.get_expected_sell_currency_amount(), 95);
+                assert_eq!(action.get_order_type(), payload::OrderType::MARKET);
+                assert_eq!(action.get_time_in_force(), payload::TimeInForce::IOC);
+            }
+            action => panic!("Unexpected action {:?}", action),
+        }
+        match decode_legacy("buy,10,USD,0,1600000000").unwrap().get_action() {
+            Action::BuyDGCoin(action) => {
+                assert_eq!(action.get_buy_amount(), 10);
+                assert_eq!(action.get_expected_buy_currency_amount(), 0);
+            }
+            action => panic!("Unexpected action {:?}", action),
+        }
+    }
+
+    #[test]
+    fn legacy_payloads_without_a_timestamp() {
+        //As sent by the 1.0 Node client
+        let payload = decode_legacy("transferDGC,10,bob").unwrap();
+        assert_eq!(payload.get_timestamp(), LEGACY_TIMESTAMP);
+        let payload = decode_legacy("sellDGC,10,USD,95").unwrap();
+        assert_eq!(payload.get_timestamp(), LEGACY_TIMESTAMP);
+        match payload.get_action() {
+            Action::SellDGCoin(action) => assert_eq!(action.get_expected_sell_currency_amount(), 95),
+            action => panic!("Unexpected action {:?}", action),
+        }
+        assert_eq!(decode_legacy("withdraw,7").unwrap().get_timestamp(), LEGACY_TIMESTAMP);
+        assert_eq!(decode_legacy("buy,10,USD").unwrap().get_timestamp(), LEGACY_TIMESTAMP);
+        assert!(decode_legacy("transfer,10,bob,0").is_err());
+    }
+
+    #[test]
+    fn invalid_legacy_payloads() {
+        assert!(decode_legacy("deposit,ten").is_err());
+        assert!(decode_legacy("applyCredit,10").is_err());
+        assert!(decode_legacy("transfer,10,,1600000000").is_err());
+        assert!(decode_legacy("deposit,10,a,b,c").is_err());
+    }
+
+    #[test]
+    fn legacy_payloads_need_version_1() {
+        assert!(DGCPayload::new(b"deposit,25", FAMILY_VERSION_2, SIGNER).is_err());
+    }
+
+    #[test]
+    fn version_1_rejects_new_actions() {
+        let payload = Action::Pause(payload::PauseAction::new()).to_payload(1600000000).write_to_bytes().unwrap();
+        assert!(DGCPayload::new(&payload, FAMILY_VERSION_1, SIGNER).is_err());
+        assert!(DGCPayload::new(&payload, FAMILY_VERSION_2, SIGNER).is_ok());
+    }
+}
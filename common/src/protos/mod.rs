@@ -0,0 +1,6 @@
+// Copyright (c) The dgc.network
+// SPDX-License-Identifier: Apache-2.0
+
+pub mod payload;
+pub mod receipt;
+pub mod state;
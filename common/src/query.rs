@@ -0,0 +1,76 @@
+// Copyright (c) The dgc.network
+// SPDX-License-Identifier: Apache-2.0
+
+//Typed views of dgc-core state decoded from the raw bytes stored at an
+//address, as served by the REST API's /state endpoint or saved to a file
+
+use std::fs;
+use std::path::Path;
+use std::str;
+
+use protobuf;
+
+use error::QueryError;
+use protos::state::{Account, ExchangeRate, Order, OrderBook};
+use state;
+
+//Resting orders at one price of a book, oldest first
+#[derive(Debug, Clone)]
+pub struct PriceLevel {
+    pub price: u32,
+    pub orders: Vec<Order>,
+}
+
+//Raw state bytes saved to a file
+pub fn read_state_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, QueryError> {
+    Ok(fs::read(path)?)
+}
+
+//Accounts still holding a legacy decimal balance decode to just the balance
+pub fn decode_account(packed: &[u8]) -> Result<Account, QueryError> {
+    state::decode_account(packed).map_err(|err| QueryError::InvalidState(format!("{}", err)))
+}
+
+//Credits are stored as UTF-8 decimal strings
+pub fn decode_credit(packed: &[u8]) -> Result<u32, QueryError> {
+    match str::from_utf8(packed).ok().and_then(|s| s.parse().ok()) {
+        Some(v) => Ok(v),
+        None => Err(QueryError::InvalidState(String::from("Cannot deserialize credit"))),
+    }
+}
+
+pub fn decode_exchange_rate(packed: &[u8]) -> Result<ExchangeRate, QueryError> {
+    protobuf::parse_from_bytes(packed)
+        .map_err(|_| QueryError::InvalidState(String::from("Cannot deserialize exchange rate")))
+}
+
+pub fn decode_order(packed: &[u8]) -> Result<Order, QueryError> {
+    protobuf::parse_from_bytes(packed).map_err(|_| QueryError::InvalidState(String::from("Cannot deserialize order")))
+}
+
+pub fn decode_order_book(packed: &[u8]) -> Result<OrderBook, QueryError> {
+    protobuf::parse_from_bytes(packed)
+        .map_err(|_| QueryError::InvalidState(String::from("Cannot deserialize order book")))
+}
+
+//Price levels of a book with its order ids resolved, best price first. Orders
+//missing from state are left out.
+pub fn resolve_order_book<F>(book: &OrderBook, mut get_order: F) -> Result<Vec<PriceLevel>, QueryError>
+where
+    F: FnMut(&str) -> Result<Option<Order>, QueryError>,
+{
+    let mut levels = Vec::new();
+    for level in book.get_levels() {
+        let mut orders = Vec::new();
+        for order_id in level.get_order_ids() {
+            if let Some(order) = get_order(order_id)? {
+                orders.push(order);
+            }
+        }
+        levels.push(PriceLevel {
+            price: level.get_price(),
+            orders: orders,
+        });
+    }
+    Ok(levels)
+}
@@ -0,0 +1,322 @@
+// Copyright (c) The dgc.network
+// SPDX-License-Identifier: Apache-2.0
+
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+use crypto::sha2::Sha512;
+
+use protobuf;
+
+use std::str;
+
+use sawtooth_sdk::processor::handler::ApplyError;
+
+use protos::state::Account;
+
+pub const FAMILY_NAME: &str = "dgc-core";
+const DGC_BALANCE: &str = "balance";
+const DGC_EXCHANGE: &str= "exchange";
+const GET_CREDIT: &str  = "credit";
+const SELL_DGC: &str = "sell";
+const BUY_DGC: &str = "buy";
+const HASH_LOCK: &str = "htlc";
+const MULTISIG: &str = "multisig";
+const MULTISIG_PROPOSAL: &str = "proposal";
+const VESTING: &str = "vesting";
+const STANDING_ORDER: &str = "standing";
+const KYC: &str = "kyc";
+const ORDER_BOOK: &str = "book";
+const POOL: &str = "pool";
+const POOL_SHARE: &str = "share";
+const TRADE: &str = "trade";
+const TRADE_SUMMARY: &str = "summary";
+const TWAP: &str = "twap";
+const PRICE_REPORT: &str = "report";
+const CIRCUIT_BREAKER: &str = "breaker";
+const PAUSE: &str = "pause";
+
+//Sawtooth settings read by the transaction processor
+pub const COMPLIANCE_KEYS_SETTING: &str = "dgc_core.compliance.allowed_keys";
+pub const KYC_VERIFIER_KEYS_SETTING: &str = "dgc_core.kyc.verifier_keys";
+//Followed by the asset, e.g. dgc_core.kyc.min_tier.DGC
+pub const KYC_MIN_TIER_SETTING: &str = "dgc_core.kyc.min_tier";
+pub const FEE_TREASURY_SETTING: &str = "dgc_core.fees.treasury";
+//Followed by the fee action and asset, e.g. dgc_core.fees.transfer.DGC,
+//with a value of "<flat>,<basis points>"
+pub const FEE_SETTING: &str = "dgc_core.fees";
+pub const TRANSFER_FEE: &str = "transfer";
+pub const TRADE_FEE: &str = "trade";
+pub const LIMIT_MAX_TRANSFER_SETTING: &str = "dgc_core.limits.max_transfer";
+pub const LIMIT_DAILY_OUTFLOW_SETTING: &str = "dgc_core.limits.daily_outflow";
+//Basis points of each swap kept by the liquidity pool
+pub const POOL_FEE_SETTING: &str = "dgc_core.pool.fee";
+pub const ORACLE_KEYS_SETTING: &str = "dgc_core.oracle.keys";
+//Seconds an oracle report stays fresh
+pub const ORACLE_MAX_AGE_SETTING: &str = "dgc_core.oracle.max_age";
+//Fresh reports needed for an oracle rate
+pub const ORACLE_QUORUM_SETTING: &str = "dgc_core.oracle.quorum";
+//Seconds of trades averaged; setting it enables the TWAP fallback
+pub const TWAP_WINDOW_SETTING: &str = "dgc_core.oracle.twap_window";
+pub const ADMIN_KEYS_SETTING: &str = "dgc_core.admin.keys";
+//Percentage a price may deviate from the reference before trading halts,
+//optionally followed by the currency, e.g. dgc_core.circuit_breaker.max_deviation.USD
+pub const MAX_DEVIATION_SETTING: &str = "dgc_core.circuit_breaker.max_deviation";
+
+const SETTINGS_NAMESPACE: &str = "000000";
+const SETTINGS_MAX_KEY_PARTS: usize = 4;
+const SETTINGS_ADDRESS_PART_SIZE: usize = 16;
+
+pub fn get_dgc_prefix() -> String {
+    let mut sha = Sha512::new();
+    sha.input_str(FAMILY_NAME);
+    sha.result_str()[..6].to_string()
+}
+
+pub fn hash(to_hash: &str, num: usize) -> String {
+    let mut sha = Sha512::new();
+    sha.input_str(to_hash);
+    let temp = sha.result_str().to_string();
+    let hash = match temp.get(..num) {
+        Some(x) => x,
+        None => "",
+    };
+    hash.to_string()
+}
+
+pub fn make_balance_state_address(identifier: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(DGC_BALANCE, 2) + &hash(identifier, 62)
+}
+
+pub fn get_balance_prefix() -> String {
+    hash(FAMILY_NAME, 6) + &hash(DGC_BALANCE, 2)
+}
+
+//Balances were stored as UTF-8 decimal strings before accounts were
+//structured. No serialized Account starts with an ASCII digit, which would
+//be an unknown field 6 or 7.
+pub fn decode_account(packed: &[u8]) -> Result<Account, ApplyError> {
+    if is_legacy_balance(packed) {
+        let value: u32 = match str::from_utf8(packed).ok().and_then(|s| s.parse().ok()) {
+            Some(v) => v,
+            None => return Err(ApplyError::InternalError(String::from("Unable to parse UTF-8 String as u32")))
+        };
+        let mut account = Account::new();
+        account.set_balance(value);
+        return Ok(account)
+    }
+    match protobuf::parse_from_bytes(packed) {
+        Ok(v) => Ok(v),
+        Err(_) => Err(ApplyError::InternalError(String::from("Cannot deserialize account")))
+    }
+}
+
+pub fn is_legacy_balance(packed: &[u8]) -> bool {
+    !packed.is_empty() && packed.iter().all(|b| b.is_ascii_digit())
+}
+
+pub fn make_exchange_state_address(currency: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(DGC_EXCHANGE, 2) + &hash(currency, 62)
+}
+
+pub fn make_credit_state_address(identifier: &str, currency: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(GET_CREDIT, 2) + &hash(currency, 2) + &hash(identifier, 60)
+}
+
+//Credits of all holders of a currency
+pub fn get_credit_prefix(currency: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(GET_CREDIT, 2) + &hash(currency, 2)
+}
+
+pub fn make_sell_state_address(currency: &str, order_id: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(SELL_DGC, 2) + &hash(currency, 2) + &hash(order_id, 60)
+}
+
+pub fn get_sell_prefix(currency: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(SELL_DGC, 2) + &hash(currency, 2)
+}
+
+pub fn make_buy_state_address(currency: &str, order_id: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(BUY_DGC, 2) + &hash(currency, 2) + &hash(order_id, 60)
+}
+
+pub fn get_buy_prefix(currency: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(BUY_DGC, 2) + &hash(currency, 2)
+}
+
+pub fn make_sell_book_state_address(currency: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(ORDER_BOOK, 2) + &hash(SELL_DGC, 2) + &hash(currency, 60)
+}
+
+pub fn make_buy_book_state_address(currency: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(ORDER_BOOK, 2) + &hash(BUY_DGC, 2) + &hash(currency, 60)
+}
+
+pub fn make_pool_state_address(currency: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(POOL, 2) + &hash(currency, 62)
+}
+
+pub fn make_pool_share_state_address(currency: &str, owner: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(POOL_SHARE, 2) + &hash(currency, 30) + &hash(owner, 32)
+}
+
+pub fn make_trade_state_address(currency: &str, trade_id: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(TRADE, 2) + &hash(currency, 2) + &hash(trade_id, 60)
+}
+
+pub fn get_trade_prefix(currency: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(TRADE, 2) + &hash(currency, 2)
+}
+
+pub fn make_trade_summary_state_address(currency: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(TRADE_SUMMARY, 2) + &hash(currency, 62)
+}
+
+pub fn make_twap_state_address(currency: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(TWAP, 2) + &hash(currency, 62)
+}
+
+pub fn make_price_report_state_address(currency: &str, oracle: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(PRICE_REPORT, 2) + &hash(currency, 2) + &hash(oracle, 60)
+}
+
+pub fn get_price_report_prefix(currency: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(PRICE_REPORT, 2) + &hash(currency, 2)
+}
+
+pub fn make_circuit_breaker_state_address(currency: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(CIRCUIT_BREAKER, 2) + &hash(currency, 62)
+}
+
+pub fn make_pause_state_address() -> String {
+    hash(FAMILY_NAME, 6) + &hash(PAUSE, 64)
+}
+
+pub fn make_hashlock_state_address(hashlock: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(HASH_LOCK, 2) + &hash(hashlock, 62)
+}
+
+pub fn make_multisig_state_address(account_id: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(MULTISIG, 2) + &hash(account_id, 62)
+}
+
+pub fn make_multisig_proposal_state_address(account_id: &str, proposal_id: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(MULTISIG_PROPOSAL, 2) + &hash(account_id, 32) + &hash(proposal_id, 30)
+}
+
+pub fn make_vesting_state_address(beneficiary: &str, grant_id: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(VESTING, 2) + &hash(beneficiary, 32) + &hash(grant_id, 30)
+}
+
+pub fn make_standing_order_state_address(payer: &str, order_id: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(STANDING_ORDER, 2) + &hash(payer, 32) + &hash(order_id, 30)
+}
+
+pub fn make_kyc_state_address(identifier: &str) -> String {
+    hash(FAMILY_NAME, 6) + &hash(KYC, 2) + &hash(identifier, 62)
+}
+
+//Address of a key in the sawtooth settings namespace
+pub fn make_settings_state_address(key: &str) -> String {
+    let mut parts: Vec<&str> = key.splitn(SETTINGS_MAX_KEY_PARTS, '.').collect();
+    while parts.len() < SETTINGS_MAX_KEY_PARTS {
+        parts.push("");
+    }
+    parts.iter().fold(SETTINGS_NAMESPACE.to_string(), |address, part| {
+        let mut sha = Sha256::new();
+        sha.input_str(part);
+        address + &sha.result_str()[..SETTINGS_ADDRESS_PART_SIZE]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use protobuf::Message;
+
+    #[test]
+    fn decimal_balances_are_legacy() {
+        assert!(is_legacy_balance(b"0"));
+        assert!(is_legacy_balance(b"1500"));
+        assert!(!is_legacy_balance(b""));
+        assert!(!is_legacy_balance(b"15a"));
+        assert!(!is_legacy_balance(b"-1"));
+    }
+
+    #[test]
+    fn decode_legacy_balance() {
+        let account = decode_account(b"1500").unwrap();
+        assert_eq!(account.get_balance(), 1500);
+        assert!(!account.get_frozen());
+        assert!(decode_account(b"99999999999").is_err());
+    }
+
+    #[test]
+    fn decode_structured_account() {
+        let mut account = Account::new();
+        account.set_balance(42);
+        account.set_frozen(true);
+        let packed = account.write_to_bytes().unwrap();
+        assert!(!is_legacy_balance(&packed));
+        assert_eq!(decode_account(&packed).unwrap(), account);
+    }
+
+    #[test]
+    fn settings_address() {
+        assert_eq!(
+            make_settings_state_address("sawtooth.settings.vote.authorized_keys"),
+            "000000a87cb5eafdcca6a8cde0fb0dec1400c5ab274474a6aa82c12840f169a04216b7"
+        );
+        //Keys of fewer than four parts are padded with empty parts
+        let address = make_settings_state_address(FEE_TREASURY_SETTING);
+        assert_eq!(address.len(), 70);
+        assert!(address.starts_with(SETTINGS_NAMESPACE));
+        assert_ne!(
+            make_settings_state_address("dgc_core.fees.transfer.DGC"),
+            make_settings_state_address("dgc_core.fees.transfer.USD")
+        );
+    }
+
+    #[test]
+    fn addresses_are_in_the_namespace() {
+        let pubkey = "02a2a0b1e5a0a0c5e5a0a0b1e5a0a0c5e5a0a0b1e5a0a0c5e5a0a0b1e5a0a0c5e5";
+        let addresses = vec![
+            make_balance_state_address(pubkey),
+            make_credit_state_address(pubkey, "USD"),
+            make_sell_state_address("USD", "1:order"),
+            make_buy_book_state_address("USD"),
+            make_multisig_proposal_state_address("treasury", "1"),
+            make_vesting_state_address(pubkey, "grant"),
+            make_standing_order_state_address(pubkey, "rent"),
+            make_price_report_state_address("USD", pubkey),
+            make_pause_state_address(),
+        ];
+        for address in addresses {
+            assert_eq!(address.len(), 70);
+            assert!(address.starts_with(&get_dgc_prefix()));
+        }
+    }
+
+    #[test]
+    fn pool_share_addresses_separate_currencies() {
+        let owner = "02a2a0b1e5a0a0c5e5a0a0b1e5a0a0c5e5a0a0b1e5a0a0c5e5a0a0b1e5a0a0c5e5";
+        //USD and CHF share their first two hash characters
+        assert_eq!(hash("USD", 2), hash("CHF", 2));
+        assert_ne!(
+            make_pool_share_state_address("USD", owner),
+            make_pool_share_state_address("CHF", owner)
+        );
+    }
+
+    #[test]
+    fn proposal_addresses_separate_accounts() {
+        assert_ne!(
+            make_multisig_proposal_state_address("treasury", "1")[..40],
+            make_multisig_proposal_state_address("payroll", "1")[..40]
+        );
+        assert_eq!(
+            make_multisig_proposal_state_address("treasury", "1")[..40],
+            make_multisig_proposal_state_address("treasury", "2")[..40]
+        );
+    }
+}
@@ -0,0 +1,604 @@
+// Copyright (c) The dgc.network
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crypto::digest::Digest;
+use crypto::sha2::Sha512;
+use protobuf;
+use protobuf::Message;
+use sawtooth_sdk::messages::batch::{Batch, BatchHeader, BatchList};
+use sawtooth_sdk::messages::transaction::{Transaction, TransactionHeader};
+use sawtooth_sdk::signing::{PrivateKey, Signer};
+use sawtooth_sdk::signing::secp256k1::Secp256k1Context;
+
+use error::TransactionError;
+use payload::{Action, FAMILY_VERSION_2};
+use protos::payload;
+use state::*;
+
+//Payload timestamps are seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+//Builds signed dgc-core transactions whose inputs and outputs are the
+//addresses the transaction processor reads and writes for the action.
+//Addresses that depend on state the client cannot see, such as the makers
+//an order fills against, are given as the narrowest prefix covering them.
+pub struct TransactionBuilder {
+    action: Action,
+    timestamp: u64,
+    treasury_pubkey: Option<String>,
+}
+
+impl TransactionBuilder {
+    pub fn new(action: Action) -> TransactionBuilder {
+        TransactionBuilder {
+            action: action,
+            timestamp: now(),
+            treasury_pubkey: None,
+        }
+    }
+
+    pub fn with_timestamp(mut self, timestamp: u64) -> TransactionBuilder {
+        self.timestamp = timestamp;
+        self
+    }
+
+    //Fees are credited to the dgc_core.fees.treasury key; knowing it narrows
+    //the fee addresses from every account to the treasury account
+    pub fn with_treasury(mut self, treasury_pubkey: &str) -> TransactionBuilder {
+        self.treasury_pubkey = Some(treasury_pubkey.to_string());
+        self
+    }
+
+    //Input and output addresses of the transaction when signed by the key
+    pub fn addresses(&self, signer_pubkey: &str) -> (Vec<String>, Vec<String>) {
+        let mut addresses = Addresses::new(self.treasury_pubkey.as_ref().map(|key| key.as_str()));
+        addresses.action(&self.action, signer_pubkey, self.timestamp);
+        (addresses.inputs, addresses.outputs)
+    }
+
+    pub fn build_transaction(&self, signer: &Signer) -> Result<Transaction, TransactionError> {
+        let public_key = signer.get_public_key()?.as_hex();
+        let payload_bytes = self.action.to_payload(self.timestamp).write_to_bytes()?;
+        let (inputs, outputs) = self.addresses(&public_key);
+
+        let mut sha = Sha512::new();
+        sha.input(&payload_bytes);
+
+        let mut txn_header = TransactionHeader::new();
+        txn_header.set_family_name(String::from(FAMILY_NAME));
+        txn_header.set_family_version(String::from(FAMILY_VERSION_2));
+        txn_header.set_nonce(format!("{}:{}", now(), public_key));
+        txn_header.set_signer_public_key(public_key.clone());
+        txn_header.set_batcher_public_key(public_key);
+        txn_header.set_inputs(protobuf::RepeatedField::from_vec(inputs));
+        txn_header.set_outputs(protobuf::RepeatedField::from_vec(outputs));
+        txn_header.set_payload_sha512(sha.result_str());
+        let txn_header_bytes = txn_header.write_to_bytes()?;
+
+        let mut txn = Transaction::new();
+        txn.set_header_signature(signer.sign(&txn_header_bytes)?);
+        txn.set_header(txn_header_bytes);
+        txn.set_payload(payload_bytes);
+        Ok(txn)
+    }
+
+    //Serialized BatchList of the transaction in a batch of its own, ready to
+    //post to the REST API's /batches
+    pub fn build_batch_list(&self, private_key: &PrivateKey) -> Result<Vec<u8>, TransactionError> {
+        let context = Secp256k1Context::new();
+        let signer = Signer::new(&context, private_key);
+        let txn = self.build_transaction(&signer)?;
+        let batch = create_batch(vec![txn], &signer)?;
+        create_batch_list(vec![batch])
+    }
+}
+
+//Batches transactions signed by the same batcher key, which execute atomically
+pub fn create_batch(transactions: Vec<Transaction>, signer: &Signer) -> Result<Batch, TransactionError> {
+    let mut batch_header = BatchHeader::new();
+    batch_header.set_signer_public_key(signer.get_public_key()?.as_hex());
+    batch_header.set_transaction_ids(protobuf::RepeatedField::from_vec(
+        transactions.iter().map(|txn| txn.get_header_signature().to_string()).collect(),
+    ));
+    let batch_header_bytes = batch_header.write_to_bytes()?;
+
+    let mut batch = Batch::new();
+    batch.set_header_signature(signer.sign(&batch_header_bytes)?);
+    batch.set_header(batch_header_bytes);
+    batch.set_transactions(protobuf::RepeatedField::from_vec(transactions));
+    Ok(batch)
+}
+
+pub fn create_batch_list(batches: Vec<Batch>) -> Result<Vec<u8>, TransactionError> {
+    let mut batch_list = BatchList::new();
+    batch_list.set_batches(protobuf::RepeatedField::from_vec(batches));
+    Ok(batch_list.write_to_bytes()?)
+}
+
+//Addresses an action touches, following what each handler action and its
+//checks read and write
+struct Addresses<'a> {
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    treasury_pubkey: Option<&'a str>,
+}
+
+impl<'a> Addresses<'a> {
+    fn new(treasury_pubkey: Option<&'a str>) -> Addresses<'a> {
+        Addresses {
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            treasury_pubkey: treasury_pubkey,
+        }
+    }
+
+    fn read(&mut self, address: String) {
+        if !self.inputs.contains(&address) {
+            self.inputs.push(address);
+        }
+    }
+
+    //Written addresses are read first by the handler
+    fn write(&mut self, address: String) {
+        if !self.outputs.contains(&address) {
+            self.outputs.push(address.clone());
+        }
+        self.read(address);
+    }
+
+    fn setting(&mut self, key: &str) {
+        self.read(make_settings_state_address(key));
+    }
+
+    fn action(&mut self, action: &Action, signer: &str, timestamp: u64) {
+        //While paused only admin actions are accepted
+        if !action.is_admin() {
+            self.read(make_pause_state_address());
+        }
+        match *action {
+            Action::ApplyCredit(ref action) => {
+                self.write(make_credit_state_address(action.get_customer_pubkey(), action.get_currency()));
+            }
+            Action::TransferDGCoin(ref action) => {
+                let customer_pubkey = action.get_customer_pubkey();
+                let beneficiary_pubkey = action.get_beneficiary_pubkey();
+                self.debit(customer_pubkey);
+                self.write(make_balance_state_address(beneficiary_pubkey));
+                self.kyc("DGC", &[customer_pubkey, beneficiary_pubkey]);
+                self.fee(TRANSFER_FEE, "DGC");
+            }
+            Action::WithdrawDGCoin(ref action) => {
+                self.debit(action.get_customer_pubkey());
+            }
+            Action::DGCBalance(ref action) => {
+                self.read(make_balance_state_address(action.get_customer_pubkey()));
+            }
+            Action::SellDGCoin(ref action) => {
+                let currency = action.get_currency();
+                let customer_pubkey = action.get_customer_pubkey();
+                let beneficiary_pubkey = action.get_beneficiary_pubkey();
+                self.debit(customer_pubkey);
+                self.write(make_credit_state_address(customer_pubkey, "DGC"));
+                self.write(make_credit_state_address(beneficiary_pubkey, currency));
+                for asset in &["DGC", currency] {
+                    self.kyc(asset, &[customer_pubkey, beneficiary_pubkey]);
+                }
+                self.trade(payload::OrderSide::BUY, currency);
+                self.fee(TRADE_FEE, currency);
+                if action.get_order_type() == payload::OrderType::LIMIT
+                    && action.get_time_in_force() == payload::TimeInForce::GTC
+                {
+                    let order_id = format!("{}:{}", timestamp, customer_pubkey);
+                    self.write(make_sell_state_address(currency, &order_id));
+                    self.write(make_sell_book_state_address(currency));
+                }
+            }
+            Action::BuyDGCoin(ref action) => {
+                let currency = action.get_currency();
+                let customer_pubkey = action.get_customer_pubkey();
+                let beneficiary_pubkey = action.get_beneficiary_pubkey();
+                self.read(make_multisig_state_address(customer_pubkey));
+                self.read(make_balance_state_address(customer_pubkey));
+                self.write(make_credit_state_address(customer_pubkey, currency));
+                self.write(make_balance_state_address(beneficiary_pubkey));
+                for asset in &["DGC", currency] {
+                    self.kyc(asset, &[customer_pubkey, beneficiary_pubkey]);
+                }
+                self.trade(payload::OrderSide::SELL, currency);
+                self.fee(TRADE_FEE, "DGC");
+                if action.get_order_type() == payload::OrderType::LIMIT
+                    && action.get_time_in_force() == payload::TimeInForce::GTC
+                {
+                    let order_id = format!("{}:{}", timestamp, customer_pubkey);
+                    self.write(make_buy_state_address(currency, &order_id));
+                    self.write(make_buy_book_state_address(currency));
+                }
+            }
+            Action::LockWithHash(ref action) => {
+                self.write(make_hashlock_state_address(action.get_hashlock()));
+                self.debit(signer);
+            }
+            //The recipient and sender are only known from the lock
+            Action::ClaimWithPreimage(ref action) => {
+                self.write(make_hashlock_state_address(action.get_hashlock()));
+                self.write(get_balance_prefix());
+            }
+            Action::RefundAfterTimeout(ref action) => {
+                self.write(make_hashlock_state_address(action.get_hashlock()));
+                self.write(get_balance_prefix());
+            }
+            Action::CreateMultisig(ref action) => {
+                self.write(make_multisig_state_address(action.get_account_id()));
+                self.read(make_balance_state_address(action.get_account_id()));
+            }
+            //Proposals execute once the threshold of approvals is reached
+            Action::ProposeMultisigTransfer(ref action) => {
+                let account_id = action.get_account_id();
+                self.read(make_multisig_state_address(account_id));
+                self.write(make_multisig_proposal_state_address(account_id, action.get_proposal_id()));
+                self.outflow(account_id);
+                self.write(make_balance_state_address(action.get_beneficiary_pubkey()));
+            }
+            //The beneficiary is only known from the proposal
+            Action::ApproveMultisigTransfer(ref action) => {
+                let account_id = action.get_account_id();
+                self.read(make_multisig_state_address(account_id));
+                self.write(make_multisig_proposal_state_address(account_id, action.get_proposal_id()));
+                self.outflow(account_id);
+                self.write(get_balance_prefix());
+            }
+            Action::CreateVestingGrant(ref action) => {
+                self.write(make_vesting_state_address(action.get_beneficiary_pubkey(), action.get_grant_id()));
+                self.debit(signer);
+            }
+            Action::ClaimVested(ref action) => {
+                self.write(make_vesting_state_address(signer, action.get_grant_id()));
+                self.write(make_balance_state_address(signer));
+            }
+            Action::CreateStandingOrder(ref action) => {
+                self.write(make_standing_order_state_address(signer, action.get_order_id()));
+            }
+            //The payee is only known from the order
+            Action::ExecuteStandingOrder(ref action) => {
+                let payer_pubkey = action.get_payer_pubkey();
+                self.write(make_standing_order_state_address(payer_pubkey, action.get_order_id()));
+                self.debit(payer_pubkey);
+                self.write(get_balance_prefix());
+            }
+            Action::CancelStandingOrder(ref action) => {
+                self.write(make_standing_order_state_address(action.get_payer_pubkey(), action.get_order_id()));
+            }
+            Action::FreezeAccount(ref action) => {
+                self.setting(COMPLIANCE_KEYS_SETTING);
+                self.write(make_balance_state_address(action.get_account_pubkey()));
+            }
+            Action::UnfreezeAccount(ref action) => {
+                self.setting(COMPLIANCE_KEYS_SETTING);
+                self.write(make_balance_state_address(action.get_account_pubkey()));
+            }
+            Action::AttestKyc(ref action) => {
+                self.setting(KYC_VERIFIER_KEYS_SETTING);
+                self.write(make_kyc_state_address(action.get_subject_pubkey()));
+            }
+            Action::SetSpendingLimits(ref action) => {
+                self.setting(COMPLIANCE_KEYS_SETTING);
+                self.write(make_balance_state_address(action.get_account_pubkey()));
+            }
+            //Only the customer that placed the order can cancel it
+            Action::CancelOrder(ref action) => {
+                let currency = action.get_currency();
+                let order_id = action.get_order_id();
+                match action.get_side() {
+                    payload::OrderSide::SELL => {
+                        self.write(make_sell_state_address(currency, order_id));
+                        self.write(make_sell_book_state_address(currency));
+                        self.write(make_balance_state_address(signer));
+                    }
+                    payload::OrderSide::BUY => {
+                        self.write(make_buy_state_address(currency, order_id));
+                        self.write(make_buy_book_state_address(currency));
+                        self.write(make_credit_state_address(signer, currency));
+                    }
+                }
+            }
+            Action::PurgeExpiredOrders(ref action) => {
+                self.book(action.get_side(), action.get_currency());
+            }
+            Action::AddLiquidity(ref action) => {
+                let currency = action.get_currency();
+                let customer_pubkey = action.get_customer_pubkey();
+                self.debit(customer_pubkey);
+                self.write(make_credit_state_address(customer_pubkey, currency));
+                for asset in &["DGC", currency] {
+                    self.kyc(asset, &[customer_pubkey]);
+                }
+                self.write(make_pool_state_address(currency));
+                self.write(make_pool_share_state_address(currency, customer_pubkey));
+            }
+            Action::RemoveLiquidity(ref action) => {
+                let currency = action.get_currency();
+                let customer_pubkey = action.get_customer_pubkey();
+                self.write(make_balance_state_address(customer_pubkey));
+                self.write(make_credit_state_address(customer_pubkey, currency));
+                self.write(make_pool_state_address(currency));
+                self.write(make_pool_share_state_address(currency, customer_pubkey));
+            }
+            Action::Swap(ref action) => {
+                let currency = action.get_currency();
+                let customer_pubkey = action.get_customer_pubkey();
+                let beneficiary_pubkey = action.get_beneficiary_pubkey();
+                match action.get_side() {
+                    payload::OrderSide::SELL => {
+                        self.debit(customer_pubkey);
+                        self.write(make_credit_state_address(beneficiary_pubkey, currency));
+                    }
+                    payload::OrderSide::BUY => {
+                        self.read(make_multisig_state_address(customer_pubkey));
+                        self.read(make_balance_state_address(customer_pubkey));
+                        self.write(make_credit_state_address(customer_pubkey, currency));
+                        self.write(make_balance_state_address(beneficiary_pubkey));
+                    }
+                }
+                for asset in &["DGC", currency] {
+                    self.kyc(asset, &[customer_pubkey, beneficiary_pubkey]);
+                }
+                self.setting(POOL_FEE_SETTING);
+                self.write(make_pool_state_address(currency));
+                self.exchange_rate(currency);
+                self.circuit_breaker(currency);
+            }
+            Action::UpdateExchangeRate(ref action) => {
+                let currency = action.get_currency();
+                self.write(make_price_report_state_address(currency, signer));
+                self.exchange_rate(currency);
+                self.write(make_exchange_state_address(currency));
+                self.circuit_breaker(currency);
+            }
+            Action::ResumeTrading(ref action) => {
+                self.setting(ADMIN_KEYS_SETTING);
+                self.write(make_circuit_breaker_state_address(action.get_currency()));
+            }
+            Action::Pause(_) | Action::Unpause(_) => {
+                self.setting(ADMIN_KEYS_SETTING);
+                self.write(make_pause_state_address());
+            }
+            Action::MigrateAccounts(ref action) => {
+                self.setting(ADMIN_KEYS_SETTING);
+                for address in action.get_addresses() {
+                    self.write(address.to_string());
+                }
+            }
+        }
+    }
+
+    //Spending from an account that may not be a multisig account
+    fn debit(&mut self, pubkey: &str) {
+        self.read(make_multisig_state_address(pubkey));
+        self.outflow(pubkey);
+    }
+
+    //Frozen check, spending limits and the balance of the account
+    fn outflow(&mut self, pubkey: &str) {
+        self.write(make_balance_state_address(pubkey));
+        self.setting(LIMIT_MAX_TRANSFER_SETTING);
+        self.setting(LIMIT_DAILY_OUTFLOW_SETTING);
+    }
+
+    fn kyc(&mut self, asset: &str, pubkeys: &[&str]) {
+        self.setting(&format!("{}.{}", KYC_MIN_TIER_SETTING, asset));
+        for pubkey in pubkeys {
+            self.read(make_kyc_state_address(pubkey));
+        }
+    }
+
+    fn fee(&mut self, action: &str, asset: &str) {
+        self.setting(FEE_TREASURY_SETTING);
+        self.setting(&format!("{}.{}.{}", FEE_SETTING, action, asset));
+        let address = match (self.treasury_pubkey, asset) {
+            (Some(treasury_pubkey), "DGC") => make_balance_state_address(treasury_pubkey),
+            (Some(treasury_pubkey), _) => make_credit_state_address(treasury_pubkey, asset),
+            (None, "DGC") => get_balance_prefix(),
+            (None, _) => get_credit_prefix(asset),
+        };
+        self.write(address);
+    }
+
+    //Matching against one side of the book and settling the fills
+    fn trade(&mut self, side: payload::OrderSide, currency: &str) {
+        self.book(side, currency);
+        self.write(get_balance_prefix());
+        self.write(get_credit_prefix(currency));
+        self.write(get_trade_prefix(currency));
+        self.write(make_trade_summary_state_address(currency));
+        self.write(make_twap_state_address(currency));
+        self.setting(TWAP_WINDOW_SETTING);
+        self.exchange_rate(currency);
+        self.circuit_breaker(currency);
+    }
+
+    //Resting orders of one side, released to their makers when they expire
+    fn book(&mut self, side: payload::OrderSide, currency: &str) {
+        match side {
+            payload::OrderSide::SELL => {
+                self.write(make_sell_book_state_address(currency));
+                self.write(get_sell_prefix(currency));
+                self.write(get_balance_prefix());
+            }
+            payload::OrderSide::BUY => {
+                self.write(make_buy_book_state_address(currency));
+                self.write(get_buy_prefix(currency));
+                self.write(get_credit_prefix(currency));
+            }
+        }
+    }
+
+    //Oracle reports, TWAP and last trade price of the currency
+    fn exchange_rate(&mut self, currency: &str) {
+        self.setting(ORACLE_KEYS_SETTING);
+        self.setting(ORACLE_MAX_AGE_SETTING);
+        self.setting(ORACLE_QUORUM_SETTING);
+        self.setting(TWAP_WINDOW_SETTING);
+        self.read(get_price_report_prefix(currency));
+        self.read(make_twap_state_address(currency));
+        self.read(make_trade_summary_state_address(currency));
+    }
+
+    fn circuit_breaker(&mut self, currency: &str) {
+        self.setting(&format!("{}.{}", MAX_DEVIATION_SETTING, currency));
+        self.setting(MAX_DEVIATION_SETTING);
+        self.write(make_circuit_breaker_state_address(currency));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUSTOMER: &str = "02a2a0b1e5a0a0c5e5a0a0b1e5a0a0c5e5a0a0b1e5a0a0c5e5a0a0b1e5a0a0c5e5";
+    const BENEFICIARY: &str = "03b3b1c2f6b1b1d6f6b1b1c2f6b1b1d6f6b1b1c2f6b1b1d6f6b1b1c2f6b1b1d6f6";
+    const TREASURY: &str = "02c4c2d3a7c2c2e7a7c2c2d3a7c2c2e7a7c2c2d3a7c2c2e7a7c2c2d3a7c2c2e7a7";
+
+    fn assert_contains(addresses: &[String], expected: &[String]) {
+        for address in expected {
+            assert!(addresses.contains(address), "{} is missing", address);
+        }
+    }
+
+    //Outputs are read as well, and nothing is outside the family and
+    //settings namespaces
+    fn assert_well_formed(inputs: &[String], outputs: &[String]) {
+        assert_contains(inputs, outputs);
+        for address in inputs {
+            assert!(address.starts_with(&get_dgc_prefix()) || address.starts_with("000000"));
+        }
+    }
+
+    fn transfer() -> Action {
+        let mut action = payload::TransferDGCoinAction::new();
+        action.set_customer_pubkey(CUSTOMER.to_string());
+        action.set_beneficiary_pubkey(BENEFICIARY.to_string());
+        action.set_transfer_amount(10);
+        Action::TransferDGCoin(action)
+    }
+
+    #[test]
+    fn transfer_addresses() {
+        let (inputs, outputs) = TransactionBuilder::new(transfer())
+            .with_treasury(TREASURY)
+            .addresses(CUSTOMER);
+        assert_well_formed(&inputs, &outputs);
+        //Pause, multisig, frozen and KYC checks, spending limits and the fee
+        assert_contains(
+            &inputs,
+            &[
+                make_pause_state_address(),
+                make_multisig_state_address(CUSTOMER),
+                make_kyc_state_address(CUSTOMER),
+                make_kyc_state_address(BENEFICIARY),
+                make_settings_state_address(&format!("{}.DGC", KYC_MIN_TIER_SETTING)),
+                make_settings_state_address(LIMIT_MAX_TRANSFER_SETTING),
+                make_settings_state_address(LIMIT_DAILY_OUTFLOW_SETTING),
+                make_settings_state_address(FEE_TREASURY_SETTING),
+                make_settings_state_address(&format!("{}.{}.DGC", FEE_SETTING, TRANSFER_FEE)),
+            ],
+        );
+        assert_eq!(
+            outputs,
+            vec![
+                make_balance_state_address(CUSTOMER),
+                make_balance_state_address(BENEFICIARY),
+                make_balance_state_address(TREASURY),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_treasury_covers_every_balance() {
+        let (inputs, outputs) = TransactionBuilder::new(transfer()).addresses(CUSTOMER);
+        assert_well_formed(&inputs, &outputs);
+        assert!(outputs.contains(&get_balance_prefix()));
+    }
+
+    #[test]
+    fn resting_sell_order_addresses() {
+        let mut action = payload::SellDGCoinAction::new();
+        action.set_customer_pubkey(CUSTOMER.to_string());
+        action.set_beneficiary_pubkey(CUSTOMER.to_string());
+        action.set_currency(String::from("USD"));
+        action.set_sell_amount(10);
+        action.set_order_type(payload::OrderType::LIMIT);
+        action.set_time_in_force(payload::TimeInForce::GTC);
+        action.set_price(100);
+        let (inputs, outputs) = TransactionBuilder::new(Action::SellDGCoin(action))
+            .with_timestamp(1600000000)
+            .with_treasury(TREASURY)
+            .addresses(CUSTOMER);
+        assert_well_formed(&inputs, &outputs);
+        //The resting order is stored at its id of timestamp and customer
+        assert_contains(
+            &outputs,
+            &[
+                make_sell_state_address("USD", &format!("1600000000:{}", CUSTOMER)),
+                make_sell_book_state_address("USD"),
+                make_buy_book_state_address("USD"),
+                make_credit_state_address(CUSTOMER, "DGC"),
+                make_credit_state_address(TREASURY, "USD"),
+                make_trade_summary_state_address("USD"),
+                make_twap_state_address("USD"),
+                make_circuit_breaker_state_address("USD"),
+            ],
+        );
+        assert_contains(
+            &inputs,
+            &[
+                get_price_report_prefix("USD"),
+                make_settings_state_address(&format!("{}.USD", KYC_MIN_TIER_SETTING)),
+                make_settings_state_address(&format!("{}.USD", MAX_DEVIATION_SETTING)),
+            ],
+        );
+    }
+
+    #[test]
+    fn signer_addresses() {
+        let mut action = payload::ClaimVestedAction::new();
+        action.set_grant_id(String::from("grant"));
+        let (inputs, outputs) = TransactionBuilder::new(Action::ClaimVested(action)).addresses(BENEFICIARY);
+        assert_well_formed(&inputs, &outputs);
+        assert_eq!(
+            outputs,
+            vec![
+                make_vesting_state_address(BENEFICIARY, "grant"),
+                make_balance_state_address(BENEFICIARY),
+            ]
+        );
+    }
+
+    #[test]
+    fn withdraw_addresses() {
+        let mut action = payload::WithdrawDGCoinAction::new();
+        action.set_customer_pubkey(CUSTOMER.to_string());
+        action.set_withdraw_amount(10);
+        let (inputs, outputs) = TransactionBuilder::new(Action::WithdrawDGCoin(action)).addresses(CUSTOMER);
+        assert_well_formed(&inputs, &outputs);
+        assert_contains(&inputs, &[make_multisig_state_address(CUSTOMER)]);
+        assert_eq!(outputs, vec![make_balance_state_address(CUSTOMER)]);
+    }
+
+    #[test]
+    fn admin_actions_ignore_the_pause() {
+        let mut action = payload::FreezeAccountAction::new();
+        action.set_account_pubkey(CUSTOMER.to_string());
+        let (inputs, outputs) = TransactionBuilder::new(Action::FreezeAccount(action)).addresses(TREASURY);
+        assert_well_formed(&inputs, &outputs);
+        assert!(!inputs.contains(&make_pause_state_address()));
+        assert_contains(&inputs, &[make_settings_state_address(COMPLIANCE_KEYS_SETTING)]);
+        assert_eq!(outputs, vec![make_balance_state_address(CUSTOMER)]);
+    }
+}
@@ -1,15 +1,73 @@
 // Copyright (c) The dgc.network
 // SPDX-License-Identifier: Apache-2.0
 
+use protobuf::Message;
+use protobuf::RepeatedField;
+use protobuf::ProtobufEnum;
+
 use sawtooth_sdk::messages::processor::TpProcessRequest;
 use sawtooth_sdk::processor::handler::ApplyError;
 use sawtooth_sdk::processor::handler::TransactionContext;
 use sawtooth_sdk::processor::handler::TransactionHandler;
 
-use handler::payload::DGCPayload;
-use handler::payload::Action;
+use dgc_core_common::payload::DGCPayload;
+use dgc_core_common::payload::Action;
+use dgc_core_common::payload::FAMILY_VERSION_1;
+use dgc_core_common::payload::FAMILY_VERSION_2;
+use dgc_core_common::state::get_dgc_prefix;
+use dgc_core_common::state::get_balance_prefix;
+use dgc_core_common::state::hash;
+use dgc_core_common::state::COMPLIANCE_KEYS_SETTING;
+use dgc_core_common::state::KYC_VERIFIER_KEYS_SETTING;
+use dgc_core_common::state::KYC_MIN_TIER_SETTING;
+use dgc_core_common::state::FEE_TREASURY_SETTING;
+use dgc_core_common::state::FEE_SETTING;
+use dgc_core_common::state::TRANSFER_FEE;
+use dgc_core_common::state::TRADE_FEE;
+use dgc_core_common::state::LIMIT_MAX_TRANSFER_SETTING;
+use dgc_core_common::state::LIMIT_DAILY_OUTFLOW_SETTING;
+use dgc_core_common::state::POOL_FEE_SETTING;
+use dgc_core_common::state::ORACLE_KEYS_SETTING;
+use dgc_core_common::state::ORACLE_MAX_AGE_SETTING;
+use dgc_core_common::state::ORACLE_QUORUM_SETTING;
+use dgc_core_common::state::TWAP_WINDOW_SETTING;
+use dgc_core_common::state::ADMIN_KEYS_SETTING;
+use dgc_core_common::state::MAX_DEVIATION_SETTING;
 use handler::state::DGCState;
-use handler::state::get_dgc_prefix;
+
+use protos::payload;
+use protos::receipt::FeeReceipt;
+use protos::state::Account;
+use protos::state::Outflow;
+use protos::state::HashLock;
+use protos::state::MultisigAccount;
+use protos::state::MultisigProposal;
+use protos::state::VestingGrant;
+use protos::state::StandingOrder;
+use protos::state::KycAttestation;
+use protos::state::Order;
+use protos::state::OrderBook;
+use protos::state::OrderBook_PriceLevel;
+use protos::state::LiquidityPool;
+use protos::state::LiquidityShare;
+use protos::state::Trade;
+use protos::state::TradeSummary;
+use protos::state::TradeSummary_Volume;
+use protos::state::ExchangeRate;
+use protos::state::PriceAccumulator;
+use protos::state::PriceAccumulator_Observation;
+use protos::state::PriceReport;
+use protos::state::TradingHalt;
+use protos::state::PauseState;
+
+const OUTFLOW_WINDOW: u64 = 24 * 60 * 60;
+const VOLUME_WINDOW: u64 = 24 * 60 * 60;
+const VOLUME_BUCKET: u64 = 60 * 60;
+//Resting orders per side of a currency's book
+const MAX_ORDER_BOOK_SIZE: usize = 100;
+const DEFAULT_POOL_FEE: u32 = 30;
+const DEFAULT_ORACLE_MAX_AGE: u64 = 60 * 60;
+const DEFAULT_ORACLE_QUORUM: usize = 1;
 
 pub struct DGCTransactionHandler {
     family_name: String,
@@ -38,14 +96,14 @@ impl DGCTransactionHandler {
     pub fn new() -> DGCTransactionHandler {
         DGCTransactionHandler {
             family_name: String::from("dgc-core"),
-            family_versions: vec![String::from("1.0")],
+            family_versions: vec![String::from(FAMILY_VERSION_1), String::from(FAMILY_VERSION_2)],
             namespaces: vec![String::from(get_dgc_prefix().to_string())],
         }
     }         
         
     fn _apply_credit(
         &self,
-        payload: handler::payload::DGCPayload::ApplyCreditAction,
+        payload: payload::ApplyCreditAction,
         mut state: DGCState,
         signer: &str,
         timestamp: u64,
@@ -71,12 +129,25 @@ impl DGCTransactionHandler {
 
     fn _transfer_dg_coin(
         &self,
-        payload: handler::payload::DGCPayload::TransferDGCoinAction,
+        payload: payload::TransferDGCoinAction,
         mut state: DGCState,
         signer: &str,
         timestamp: u64,
     ) -> Result<(), ApplyError> {
         let customer_pubkey = payload.get_customer_pubkey();
+        //Only the customer can spend from their account
+        if signer != customer_pubkey {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: TransferDGCoin. Signer is not the customer.",
+            )))
+        }
+        //Multisig accounts can only be spent through approved proposals
+        if state.get_multisig(customer_pubkey)?.is_some() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: TransferDGCoin. Multisig account requires an approved proposal.",
+            )))
+        }
+        self._check_not_frozen(&mut state, customer_pubkey)?;
         //Get balance of customer
         let customer_balance: u32 = match state.get_balance(customer_pubkey) {
             Ok(Some(v)) => v,
@@ -98,32 +169,111 @@ impl DGCTransactionHandler {
             Err(err) => return Err(err),
         };
         
+        //Both parties should hold the KYC tier required for DGC
+        self._check_kyc_tier(&mut state, "DGC", customer_pubkey, timestamp)?;
+        self._check_kyc_tier(&mut state, "DGC", beneficiary_pubkey, timestamp)?;
+
         let transfer_amount = payload.get_transfer_amount();
-        //Transfer amount should not be greater than current account balance        
-        if transfer_amount > customer_balance {
+        //Fee is charged on top of the transfer amount
+        let fee = self._get_fee(&mut state, TRANSFER_FEE, "DGC", transfer_amount)?;
+        //Transfer amount plus fee should not be greater than current account balance        
+        if u64::from(transfer_amount) + u64::from(fee) > u64::from(customer_balance) {
             return Err(ApplyError::InvalidTransaction(String::from(
                 "Action: Transfer amount is more than customer account balance.",
             )))
         }
         
+        //Outflow should stay within the spending limits of the customer
+        self._record_outflow(&mut state, customer_pubkey, transfer_amount + fee, timestamp)?;
+
         //Store new balance to state
-        let new_customer_balance = customer_balance - transfer_amount;
+        let new_customer_balance = customer_balance - transfer_amount - fee;
         let new_beneficiary_balance = beneficiary_balance + transfer_amount;
         state.set_balance(customer_pubkey, new_customer_balance)?;
         state.set_balance(beneficiary_pubkey, new_beneficiary_balance)?;
+        self._collect_fee(&mut state, TRANSFER_FEE, "DGC", customer_pubkey, fee)?;
                                      
         Ok(())    
     }
 
+    fn _withdraw_dg_coin(
+        &self,
+        payload: payload::WithdrawDGCoinAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let customer_pubkey = payload.get_customer_pubkey();
+        //Only the customer can spend from their account
+        if signer != customer_pubkey {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: WithdrawDGCoin. Signer is not the customer.",
+            )))
+        }
+        //Multisig accounts can only be spent through approved proposals
+        if state.get_multisig(customer_pubkey)?.is_some() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: WithdrawDGCoin. Multisig account requires an approved proposal.",
+            )))
+        }
+        self._check_not_frozen(&mut state, customer_pubkey)?;
+        //Get balance of customer
+        let customer_balance: u32 = state.get_balance(customer_pubkey)?.unwrap_or(0);
+
+        let withdraw_amount = payload.get_withdraw_amount();
+        //Withdraw amount should not be greater than current account balance
+        if withdraw_amount > customer_balance {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: Withdraw amount is more than account balance.",
+            )))
+        }
+
+        //Outflow should stay within the spending limits of the customer
+        self._record_outflow(&mut state, customer_pubkey, withdraw_amount, timestamp)?;
+
+        //Store new balance to state
+        state.set_balance(customer_pubkey, customer_balance - withdraw_amount)?;
+
+        Ok(())
+    }
+
+    fn _dgc_balance(
+        &self,
+        payload: payload::DGCBalanceAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let customer_pubkey = payload.get_customer_pubkey();
+        let customer_balance: u32 = state.get_balance(customer_pubkey)?.unwrap_or(0);
+        info!("current balance: {} ", customer_balance);
+
+        Ok(())
+    }
+
     fn _sell_dg_coin(
         &self,
-        payload: handler::payload::DGCPayload::SellDGCoinAction,
+        payload: payload::SellDGCoinAction,
         mut state: DGCState,
         signer: &str,
         timestamp: u64,
     ) -> Result<(), ApplyError> {
         let currency = payload.get_currency();
         let customer_pubkey = payload.get_customer_pubkey();
+        //Only the customer can spend from their account
+        if signer != customer_pubkey {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: SellDGCoin. Signer is not the customer.",
+            )))
+        }
+        //Multisig accounts can only be spent through approved proposals
+        if state.get_multisig(customer_pubkey)?.is_some() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: SellDGCoin. Multisig account requires an approved proposal.",
+            )))
+        }
+        self._check_not_frozen(&mut state, customer_pubkey)?;
+        self._check_not_halted(&mut state, currency)?;
         //Get balance of customer
         let customer_balance: u32 = match state.get_balance(customer_pubkey) {
             Ok(Some(v)) => v,
@@ -140,167 +290,2388 @@ impl DGCTransactionHandler {
                 info!("Creating new currency for user.");
                 0              
             }
-            Err(err) => return Err(err),
-        };                                
-                                
-        let sell_amount = payload.get_sell_amount();
-        //Sell amount should not be greater than current account balance + customer_credit
-        if sell_amount > customer_balance + customer_credit{
-            return Err(ApplyError::InvalidTransaction(String::from(
-                "Action: Transfer amount is more than customer account balance.",
-            )))
+            Err(err) => return Err(err),
+        };                                
+                                
+        let sell_amount = payload.get_sell_amount();
+        if payload.get_expiry() != 0 && payload.get_expiry() <= timestamp {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: SellDGCoin. Order has already expired.",
+            )))
+        }
+        if sell_amount == 0 {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: SellDGCoin. Sell amount must be greater than zero.",
+            )))
+        }
+        //Sell amount should not be greater than current account balance + customer_credit
+        if u64::from(sell_amount) > u64::from(customer_balance) + u64::from(customer_credit) {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: Sell amount is more than customer account balance.",
+            )))
+        }
+        //Limit orders only fill at their price or better
+        let limit_price = match payload.get_order_type() {
+            payload::OrderType::LIMIT if payload.get_price() == 0 => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: SellDGCoin. Limit order requires a price.",
+                )))
+            }
+            payload::OrderType::LIMIT => Some(payload.get_price()),
+            payload::OrderType::MARKET => None,
+        };
+
+        let beneficiary_pubkey = payload.get_beneficiary_pubkey();
+        //Both parties should hold the KYC tiers required for DGC and the currency
+        for asset in &["DGC", currency] {
+            self._check_kyc_tier(&mut state, asset, customer_pubkey, timestamp)?;
+            self._check_kyc_tier(&mut state, asset, beneficiary_pubkey, timestamp)?;
+        }
+        //Match against resting buy orders
+        let fills = self._match_orders(
+            &mut state,
+            payload::OrderSide::BUY,
+            currency,
+            limit_price,
+            sell_amount,
+            customer_pubkey,
+            timestamp,
+        )?;
+        //A fill far from the reference price halts trading instead of executing
+        let reference_price = self._get_exchange_rate(&mut state, currency, timestamp)?;
+        for &(ref order, _) in &fills {
+            if self._trip_circuit_breaker(&mut state, currency, u64::from(order.get_price()), 1, reference_price, timestamp)? {
+                return Ok(())
+            }
+        }
+        let filled_amount = fills.iter().fold(0, |total, &(_, quantity)| total + quantity);
+        //Fill or kill orders are rejected unless completely filled
+        if payload.get_time_in_force() == payload::TimeInForce::FOK && filled_amount < sell_amount {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: SellDGCoin. Fill or kill order cannot be completely filled.",
+            )))
+        }
+        //Only good till cancelled limit orders rest on the book
+        let resting_amount = match (limit_price, payload.get_time_in_force()) {
+            (Some(_), payload::TimeInForce::GTC) => sell_amount - filled_amount,
+            _ => 0,
+        };
+
+        //Expected sell currency amount is the minimum received for the whole
+        //sell amount; market orders without one are bounded by the exchange rate
+        let expected_sell_currency_amount = match payload.get_expected_sell_currency_amount() {
+            0 if limit_price.is_none() => {
+                u64::from(self._get_exchange_rate(&mut state, currency, timestamp)?) * u64::from(sell_amount)
+            }
+            x => u64::from(x),
+        };
+        //Realized value of the fills at the maker price and of the resting amount at the limit price
+        let realized_amount = fills
+            .iter()
+            .fold(0, |total, &(ref order, quantity)| total + u64::from(quantity) * u64::from(order.get_price()))
+            + u64::from(resting_amount) * u64::from(payload.get_price());
+        if u128::from(realized_amount) * u128::from(sell_amount)
+            < u128::from(expected_sell_currency_amount) * u128::from(filled_amount + resting_amount)
+        {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: SellDGCoin. Realized price is below the expected sell currency amount.",
+            )))
+        }
+
+        //Debit filled and resting amount, from balance first and then from credit
+        let debit_amount = filled_amount + resting_amount;
+        self._record_outflow(&mut state, customer_pubkey, debit_amount, timestamp)?;
+        let balance_debit = debit_amount.min(customer_balance);
+        state.set_balance(customer_pubkey, customer_balance - balance_debit)?;
+        if debit_amount > balance_debit {
+            state.set_credit(customer_pubkey, "DGC", customer_credit - (debit_amount - balance_debit))?;
+        }
+
+        //Settle each fill at the maker price
+        let mut proceeds: u64 = 0;
+        for (order, quantity) in fills {
+            proceeds += self._fill_order(&mut state, payload::OrderSide::BUY, currency, order, quantity, customer_pubkey, timestamp)?;
+        }
+        if proceeds > u64::from(u32::max_value()) {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: SellDGCoin. Proceeds exceed the maximum amount.",
+            )))
+        }
+        let proceeds = proceeds as u32;
+        let fee = self._get_fee(&mut state, TRADE_FEE, currency, proceeds)?.min(proceeds);
+        let beneficiary_credit = state.get_credit(beneficiary_pubkey, currency)?.unwrap_or(0);
+        let new_beneficiary_credit = match beneficiary_credit.checked_add(proceeds - fee) {
+            Some(v) => v,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: SellDGCoin. Proceeds exceed the maximum credit of beneficiary.",
+                )))
+            }
+        };
+        state.set_credit(beneficiary_pubkey, currency, new_beneficiary_credit)?;
+        self._collect_fee(&mut state, TRADE_FEE, currency, customer_pubkey, fee)?;
+
+        if resting_amount > 0 {
+            let mut order = Order::new();
+            order.set_order_id(format!("{}:{}", timestamp, customer_pubkey));
+            order.set_customer_pubkey(customer_pubkey.to_string());
+            order.set_beneficiary_pubkey(beneficiary_pubkey.to_string());
+            order.set_currency(currency.to_string());
+            order.set_price(payload.get_price());
+            order.set_quantity(resting_amount);
+            order.set_reserved(resting_amount);
+            order.set_timestamp(timestamp);
+            order.set_expiry(payload.get_expiry());
+            if state.get_sell(currency, order.get_order_id())?.is_some() {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: SellDGCoin. Order already exists.",
+                )))
+            }
+            self._add_to_book(&mut state, payload::OrderSide::SELL, &order)?;
+            state.set_sell(order)?;
+        }
+
+        Ok(())    
+    }
+
+    fn _buy_dg_coin(
+        &self,
+        payload: payload::BuyDGCoinAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let currency = payload.get_currency();
+        let customer_pubkey = payload.get_customer_pubkey();
+        //Only the customer can spend from their account
+        if signer != customer_pubkey {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: BuyDGCoin. Signer is not the customer.",
+            )))
+        }
+        //Multisig accounts can only be spent through approved proposals
+        if state.get_multisig(customer_pubkey)?.is_some() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: BuyDGCoin. Multisig account requires an approved proposal.",
+            )))
+        }
+        self._check_not_frozen(&mut state, customer_pubkey)?;
+        self._check_not_halted(&mut state, currency)?;
+        //Get currency credit of customer
+        let customer_credit: u32 = match state.get_credit(customer_pubkey, currency) {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                info!("Creating new currency for user.");
+                0              
+            }
+            Err(err) => return Err(err),
+        };                                
+                                
+        let buy_amount = payload.get_buy_amount();
+        if payload.get_expiry() != 0 && payload.get_expiry() <= timestamp {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: BuyDGCoin. Order has already expired.",
+            )))
+        }
+        if buy_amount == 0 {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: BuyDGCoin. Buy amount must be greater than zero.",
+            )))
+        }
+        //Limit orders only fill at their price or better
+        let limit_price = match payload.get_order_type() {
+            payload::OrderType::LIMIT if payload.get_price() == 0 => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: BuyDGCoin. Limit order requires a price.",
+                )))
+            }
+            payload::OrderType::LIMIT => Some(payload.get_price()),
+            payload::OrderType::MARKET => None,
+        };
+
+        let beneficiary_pubkey = payload.get_beneficiary_pubkey();
+        //Both parties should hold the KYC tiers required for DGC and the currency
+        for asset in &["DGC", currency] {
+            self._check_kyc_tier(&mut state, asset, customer_pubkey, timestamp)?;
+            self._check_kyc_tier(&mut state, asset, beneficiary_pubkey, timestamp)?;
+        }
+        //Match against resting sell orders
+        let fills = self._match_orders(
+            &mut state,
+            payload::OrderSide::SELL,
+            currency,
+            limit_price,
+            buy_amount,
+            customer_pubkey,
+            timestamp,
+        )?;
+        //A fill far from the reference price halts trading instead of executing
+        let reference_price = self._get_exchange_rate(&mut state, currency, timestamp)?;
+        for &(ref order, _) in &fills {
+            if self._trip_circuit_breaker(&mut state, currency, u64::from(order.get_price()), 1, reference_price, timestamp)? {
+                return Ok(())
+            }
+        }
+        let filled_amount = fills.iter().fold(0, |total, &(_, quantity)| total + quantity);
+        //Fill or kill orders are rejected unless completely filled
+        if payload.get_time_in_force() == payload::TimeInForce::FOK && filled_amount < buy_amount {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: BuyDGCoin. Fill or kill order cannot be completely filled.",
+            )))
+        }
+        //Only good till cancelled limit orders rest on the book
+        let resting_amount = match (limit_price, payload.get_time_in_force()) {
+            (Some(_), payload::TimeInForce::GTC) => buy_amount - filled_amount,
+            _ => 0,
+        };
+
+        //Cost of the fills at the maker price plus the reserve of the resting amount
+        let cost = fills
+            .iter()
+            .fold(0, |total, &(ref order, quantity)| total + u64::from(quantity) * u64::from(order.get_price()));
+        let reserve = u64::from(resting_amount) * u64::from(payload.get_price());
+        if cost + reserve > u64::from(customer_credit) {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: Buy amount is more than customer account credit.",
+            )))
+        }
+
+        //Expected buy currency amount is the maximum paid for the whole buy
+        //amount; market orders without one are bounded by the exchange rate
+        let expected_buy_currency_amount = match payload.get_expected_buy_currency_amount() {
+            0 => match (limit_price, self._get_exchange_rate(&mut state, currency, timestamp)?) {
+                (None, exchange_rate) if exchange_rate > 0 => u64::from(exchange_rate) * u64::from(buy_amount),
+                _ => u64::max_value(),
+            },
+            x => u64::from(x),
+        };
+        if u128::from(cost + reserve) * u128::from(buy_amount)
+            > u128::from(expected_buy_currency_amount) * u128::from(filled_amount + resting_amount)
+        {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: BuyDGCoin. Realized price is above the expected buy currency amount.",
+            )))
+        }
+        state.set_credit(customer_pubkey, currency, customer_credit - (cost + reserve) as u32)?;
+
+        //Settle each fill at the maker price
+        for (order, quantity) in fills {
+            self._fill_order(&mut state, payload::OrderSide::SELL, currency, order, quantity, customer_pubkey, timestamp)?;
+        }
+        let fee = self._get_fee(&mut state, TRADE_FEE, "DGC", filled_amount)?.min(filled_amount);
+        let beneficiary_balance = state.get_balance(beneficiary_pubkey)?.unwrap_or(0);
+        let new_beneficiary_balance = match beneficiary_balance.checked_add(filled_amount - fee) {
+            Some(v) => v,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: BuyDGCoin. Filled amount exceeds the maximum balance of beneficiary.",
+                )))
+            }
+        };
+        state.set_balance(beneficiary_pubkey, new_beneficiary_balance)?;
+        self._collect_fee(&mut state, TRADE_FEE, "DGC", customer_pubkey, fee)?;
+
+        if resting_amount > 0 {
+            let mut order = Order::new();
+            order.set_order_id(format!("{}:{}", timestamp, customer_pubkey));
+            order.set_customer_pubkey(customer_pubkey.to_string());
+            order.set_beneficiary_pubkey(beneficiary_pubkey.to_string());
+            order.set_currency(currency.to_string());
+            order.set_price(payload.get_price());
+            order.set_quantity(resting_amount);
+            order.set_reserved(reserve as u32);
+            order.set_timestamp(timestamp);
+            order.set_expiry(payload.get_expiry());
+            if state.get_buy(currency, order.get_order_id())?.is_some() {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: BuyDGCoin. Order already exists.",
+                )))
+            }
+            self._add_to_book(&mut state, payload::OrderSide::BUY, &order)?;
+            state.set_buy(order)?;
+        }
+
+        Ok(())    
+    }
+
+    fn _purge_expired_orders(
+        &self,
+        payload: payload::PurgeExpiredOrdersAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let currency = payload.get_currency();
+        let side = payload.get_side();
+        let mut purged = 0;
+        let book = self._get_book(&mut state, side, currency)?;
+        let order_ids: Vec<String> = book
+            .get_levels()
+            .iter()
+            .flat_map(|level| level.get_order_ids().iter().cloned())
+            .collect();
+        for order_id in &order_ids {
+            let order = match side {
+                payload::OrderSide::SELL => state.get_sell(currency, order_id)?,
+                payload::OrderSide::BUY => state.get_buy(currency, order_id)?,
+            };
+            match order {
+                Some(ref order) if is_expired(order, timestamp) => {
+                    self._release_order(&mut state, side, order.clone())?;
+                    purged += 1;
+                }
+                _ => (),
+            }
+        }
+        if purged == 0 {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: PurgeExpiredOrders. No expired orders to purge.",
+            )))
+        }
+        info!("Purged {} expired orders.", purged);
+
+        Ok(())
+    }
+
+    fn _cancel_order(
+        &self,
+        payload: payload::CancelOrderAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let currency = payload.get_currency();
+        let order = match payload.get_side() {
+            payload::OrderSide::SELL => state.get_sell(currency, payload.get_order_id())?,
+            payload::OrderSide::BUY => state.get_buy(currency, payload.get_order_id())?,
+        };
+        let order = match order {
+            Some(order) => order,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: CancelOrder. Order doesn't exist.",
+                )))
+            }
+        };
+        //Only the customer that placed the order can cancel it
+        if signer != order.get_customer_pubkey() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CancelOrder. Signer did not place the order.",
+            )))
+        }
+
+        self._release_order(&mut state, payload.get_side(), order)
+    }
+
+    fn _add_liquidity(
+        &self,
+        payload: payload::AddLiquidityAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let currency = payload.get_currency();
+        let customer_pubkey = payload.get_customer_pubkey();
+        //Only the customer can spend from their account
+        if signer != customer_pubkey {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: AddLiquidity. Signer is not the customer.",
+            )))
+        }
+        //Multisig accounts can only be spent through approved proposals
+        if state.get_multisig(customer_pubkey)?.is_some() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: AddLiquidity. Multisig account requires an approved proposal.",
+            )))
+        }
+        self._check_not_frozen(&mut state, customer_pubkey)?;
+        for asset in &["DGC", currency] {
+            self._check_kyc_tier(&mut state, asset, customer_pubkey, timestamp)?;
+        }
+
+        let dgc_amount = payload.get_dgc_amount();
+        if dgc_amount == 0 {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: AddLiquidity. DGC amount must be greater than zero.",
+            )))
+        }
+        let mut pool = match state.get_pool(currency)? {
+            Some(pool) => pool,
+            None => {
+                let mut pool = LiquidityPool::new();
+                pool.set_currency(currency.to_string());
+                pool
+            }
+        };
+        //The first deposit sets the pool ratio and mints the geometric mean of
+        //both amounts; later deposits follow the ratio, rounding in favour of the pool
+        let (currency_amount, shares) = if pool.get_total_shares() == 0 {
+            let currency_amount = u64::from(payload.get_currency_amount());
+            (currency_amount, isqrt(u64::from(dgc_amount) * currency_amount))
+        } else {
+            let dgc_reserve = u64::from(pool.get_dgc_reserve());
+            let currency_amount =
+                (u64::from(dgc_amount) * u64::from(pool.get_currency_reserve()) + dgc_reserve - 1) / dgc_reserve;
+            (currency_amount, u64::from(dgc_amount) * u64::from(pool.get_total_shares()) / dgc_reserve)
+        };
+        if currency_amount == 0 || currency_amount > u64::from(payload.get_currency_amount()) {
+            return Err(ApplyError::InvalidTransaction(format!(
+                "Action: AddLiquidity. Deposit requires {} {}.",
+                currency_amount, currency
+            )))
+        }
+        if shares == 0 || shares < u64::from(payload.get_min_shares()) {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: AddLiquidity. Deposit mints fewer than the minimum shares.",
+            )))
+        }
+        let dgc_reserve = u64::from(pool.get_dgc_reserve()) + u64::from(dgc_amount);
+        let currency_reserve = u64::from(pool.get_currency_reserve()) + currency_amount;
+        let total_shares = u64::from(pool.get_total_shares()) + shares;
+        if dgc_reserve.max(currency_reserve).max(total_shares) > u64::from(u32::max_value()) {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: AddLiquidity. Pool reserve exceeds the maximum amount.",
+            )))
+        }
+
+        let customer_balance = state.get_balance(customer_pubkey)?.unwrap_or(0);
+        let customer_credit = state.get_credit(customer_pubkey, currency)?.unwrap_or(0);
+        if dgc_amount > customer_balance {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: AddLiquidity. DGC amount is more than customer account balance.",
+            )))
+        }
+        if currency_amount > u64::from(customer_credit) {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: AddLiquidity. Currency amount is more than customer credit.",
+            )))
+        }
+        self._record_outflow(&mut state, customer_pubkey, dgc_amount, timestamp)?;
+        state.set_balance(customer_pubkey, customer_balance - dgc_amount)?;
+        state.set_credit(customer_pubkey, currency, customer_credit - currency_amount as u32)?;
+
+        pool.set_dgc_reserve(dgc_reserve as u32);
+        pool.set_currency_reserve(currency_reserve as u32);
+        pool.set_total_shares(total_shares as u32);
+        state.set_pool(pool)?;
+        let mut share = match state.get_pool_share(currency, customer_pubkey)? {
+            Some(share) => share,
+            None => {
+                let mut share = LiquidityShare::new();
+                share.set_currency(currency.to_string());
+                share.set_owner_pubkey(customer_pubkey.to_string());
+                share
+            }
+        };
+        let owner_shares = share.get_shares() + shares as u32;
+        share.set_shares(owner_shares);
+        state.set_pool_share(share)?;
+        info!("Minted {} shares of the {} pool.", shares, currency);
+
+        Ok(())
+    }
+
+    fn _remove_liquidity(
+        &self,
+        payload: payload::RemoveLiquidityAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let currency = payload.get_currency();
+        let customer_pubkey = payload.get_customer_pubkey();
+        //Only the owner of the shares can redeem them
+        if signer != customer_pubkey {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: RemoveLiquidity. Signer does not own the shares.",
+            )))
+        }
+        self._check_not_frozen(&mut state, customer_pubkey)?;
+
+        let shares = payload.get_shares();
+        let mut share = match state.get_pool_share(currency, customer_pubkey)? {
+            Some(ref share) if shares > 0 && share.get_shares() >= shares => share.clone(),
+            _ => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: RemoveLiquidity. Not enough shares to redeem.",
+                )))
+            }
+        };
+        let mut pool = match state.get_pool(currency)? {
+            Some(pool) => pool,
+            None => {
+                return Err(ApplyError::InvalidTransaction(format!(
+                    "Pool of {} doesn't exist",
+                    currency
+                )))
+            }
+        };
+        //Redeemed amounts round down in favour of the pool
+        let total_shares = u64::from(pool.get_total_shares());
+        let dgc_amount = (u64::from(shares) * u64::from(pool.get_dgc_reserve()) / total_shares) as u32;
+        let currency_amount = (u64::from(shares) * u64::from(pool.get_currency_reserve()) / total_shares) as u32;
+        if dgc_amount < payload.get_min_dgc_amount() || currency_amount < payload.get_min_currency_amount() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: RemoveLiquidity. Redeemed amounts are below the minimum.",
+            )))
+        }
+
+        let dgc_reserve = pool.get_dgc_reserve() - dgc_amount;
+        let currency_reserve = pool.get_currency_reserve() - currency_amount;
+        pool.set_dgc_reserve(dgc_reserve);
+        pool.set_currency_reserve(currency_reserve);
+        pool.set_total_shares(total_shares as u32 - shares);
+        state.set_pool(pool)?;
+        let owner_shares = share.get_shares() - shares;
+        share.set_shares(owner_shares);
+        state.set_pool_share(share)?;
+
+        let overflow = || ApplyError::InvalidTransaction(String::from(
+            "Action: RemoveLiquidity. Redeemed amounts exceed the maximum amount of customer.",
+        ));
+        let customer_balance = state.get_balance(customer_pubkey)?.unwrap_or(0);
+        let customer_credit = state.get_credit(customer_pubkey, currency)?.unwrap_or(0);
+        state.set_balance(customer_pubkey, customer_balance.checked_add(dgc_amount).ok_or_else(overflow)?)?;
+        state.set_credit(customer_pubkey, currency, customer_credit.checked_add(currency_amount).ok_or_else(overflow)?)?;
+
+        Ok(())
+    }
+
+    fn _swap(
+        &self,
+        payload: payload::SwapAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let currency = payload.get_currency();
+        let customer_pubkey = payload.get_customer_pubkey();
+        let beneficiary_pubkey = payload.get_beneficiary_pubkey();
+        //Only the customer can spend from their account
+        if signer != customer_pubkey {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: Swap. Signer is not the customer.",
+            )))
+        }
+        //Multisig accounts can only be spent through approved proposals
+        if state.get_multisig(customer_pubkey)?.is_some() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: Swap. Multisig account requires an approved proposal.",
+            )))
+        }
+        self._check_not_frozen(&mut state, customer_pubkey)?;
+        self._check_not_halted(&mut state, currency)?;
+        //Both parties should hold the KYC tiers required for DGC and the currency
+        for asset in &["DGC", currency] {
+            self._check_kyc_tier(&mut state, asset, customer_pubkey, timestamp)?;
+            self._check_kyc_tier(&mut state, asset, beneficiary_pubkey, timestamp)?;
+        }
+
+        let amount_in = payload.get_amount_in();
+        if amount_in == 0 {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: Swap. Amount in must be greater than zero.",
+            )))
+        }
+        let mut pool = match state.get_pool(currency)? {
+            Some(ref pool) if pool.get_dgc_reserve() > 0 && pool.get_currency_reserve() > 0 => pool.clone(),
+            _ => {
+                return Err(ApplyError::InvalidTransaction(format!(
+                    "Action: Swap. Pool of {} has no liquidity.",
+                    currency
+                )))
+            }
+        };
+        let fee_basis_points = self._get_pool_fee(&mut state)?;
+        let (reserve_in, reserve_out) = match payload.get_side() {
+            payload::OrderSide::SELL => (pool.get_dgc_reserve(), pool.get_currency_reserve()),
+            payload::OrderSide::BUY => (pool.get_currency_reserve(), pool.get_dgc_reserve()),
+        };
+        if u64::from(reserve_in) + u64::from(amount_in) > u64::from(u32::max_value()) {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: Swap. Pool reserve exceeds the maximum amount.",
+            )))
+        }
+        let amount_out = swap_output(amount_in, reserve_in, reserve_out, fee_basis_points);
+        if amount_out == 0 || amount_out < payload.get_min_amount_out() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: Swap. Amount out is below the minimum amount out.",
+            )))
+        }
+        //A swap far from the reference price halts trading instead of executing
+        let (currency_amount, dgc_amount) = match payload.get_side() {
+            payload::OrderSide::SELL => (amount_out, amount_in),
+            payload::OrderSide::BUY => (amount_in, amount_out),
+        };
+        let reference_price = self._get_exchange_rate(&mut state, currency, timestamp)?;
+        if self._trip_circuit_breaker(&mut state, currency, u64::from(currency_amount), u64::from(dgc_amount), reference_price, timestamp)? {
+            return Ok(())
+        }
+
+        let overflow = || ApplyError::InvalidTransaction(String::from(
+            "Action: Swap. Amount exceeds the maximum amount of beneficiary or pool.",
+        ));
+        match payload.get_side() {
+            payload::OrderSide::SELL => {
+                let customer_balance = state.get_balance(customer_pubkey)?.unwrap_or(0);
+                if amount_in > customer_balance {
+                    return Err(ApplyError::InvalidTransaction(String::from(
+                        "Action: Swap. Amount in is more than customer account balance.",
+                    )))
+                }
+                self._record_outflow(&mut state, customer_pubkey, amount_in, timestamp)?;
+                state.set_balance(customer_pubkey, customer_balance - amount_in)?;
+                let beneficiary_credit = state.get_credit(beneficiary_pubkey, currency)?.unwrap_or(0);
+                state.set_credit(beneficiary_pubkey, currency, beneficiary_credit.checked_add(amount_out).ok_or_else(overflow)?)?;
+                pool.set_dgc_reserve(reserve_in.checked_add(amount_in).ok_or_else(overflow)?);
+                pool.set_currency_reserve(reserve_out - amount_out);
+            }
+            payload::OrderSide::BUY => {
+                let customer_credit = state.get_credit(customer_pubkey, currency)?.unwrap_or(0);
+                if amount_in > customer_credit {
+                    return Err(ApplyError::InvalidTransaction(String::from(
+                        "Action: Swap. Amount in is more than customer credit.",
+                    )))
+                }
+                state.set_credit(customer_pubkey, currency, customer_credit - amount_in)?;
+                let beneficiary_balance = state.get_balance(beneficiary_pubkey)?.unwrap_or(0);
+                state.set_balance(beneficiary_pubkey, beneficiary_balance.checked_add(amount_out).ok_or_else(overflow)?)?;
+                pool.set_currency_reserve(reserve_in.checked_add(amount_in).ok_or_else(overflow)?);
+                pool.set_dgc_reserve(reserve_out - amount_out);
+            }
+        }
+        state.set_pool(pool)?;
+
+        Ok(())
+    }
+
+    fn _lock_with_hash(
+        &self,
+        payload: payload::LockWithHashAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let hashlock = payload.get_hashlock();
+        //Hashlock should be a hex encoded SHA-512 digest
+        if hashlock.len() != 128 {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: LockWithHash. Hashlock is not a SHA-512 digest.",
+            )))
+        }
+        //Timeout should be later than the transaction timestamp
+        if payload.get_timeout() <= timestamp {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: LockWithHash. Timeout has already passed.",
+            )))
+        }
+        //Only one lock can be open for a hashlock
+        if state.get_hashlock(hashlock)?.is_some() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: LockWithHash. Hashlock is already in use.",
+            )))
+        }
+        //Multisig accounts can only be spent through approved proposals
+        if state.get_multisig(signer)?.is_some() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: LockWithHash. Multisig account requires an approved proposal.",
+            )))
+        }
+        self._check_not_frozen(&mut state, signer)?;
+        //Get balance of sender
+        let sender_balance: u32 = match state.get_balance(signer) {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                info!("Creating new account for user.");
+                0
+            }
+            Err(err) => return Err(err),
+        };
+
+        let amount = payload.get_amount();
+        //Lock amount should not be greater than current account balance
+        if amount > sender_balance {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: LockWithHash. Lock amount is more than sender account balance.",
+            )))
+        }
+
+        //Outflow should stay within the spending limits of the sender
+        self._record_outflow(&mut state, signer, amount, timestamp)?;
+
+        //Move amount from sender balance into the lock
+        let mut lock = HashLock::new();
+        lock.set_hashlock(hashlock.to_string());
+        lock.set_sender_pubkey(signer.to_string());
+        lock.set_recipient_pubkey(payload.get_recipient_pubkey().to_string());
+        lock.set_amount(amount);
+        lock.set_timeout(payload.get_timeout());
+        state.set_balance(signer, sender_balance - amount)?;
+        state.set_hashlock(lock)?;
+
+        Ok(())
+    }
+
+    fn _claim_with_preimage(
+        &self,
+        payload: payload::ClaimWithPreimageAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let lock = match state.get_hashlock(payload.get_hashlock())? {
+            Some(lock) => lock,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: ClaimWithPreimage. Hash lock doesn't exist.",
+                )))
+            }
+        };
+        //Lock can only be claimed before its timeout
+        if timestamp >= lock.get_timeout() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ClaimWithPreimage. Hash lock has expired.",
+            )))
+        }
+        //Preimage should hash to the hashlock
+        if hash(payload.get_preimage(), 128) != lock.get_hashlock() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ClaimWithPreimage. Preimage doesn't match hashlock.",
+            )))
+        }
+
+        let recipient_pubkey = lock.get_recipient_pubkey();
+        //Get recipient balance
+        let recipient_balance: u32 = match state.get_balance(recipient_pubkey) {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                info!("Creating new account for user.");
+                0
+            }
+            Err(err) => return Err(err),
+        };
+
+        //Release locked amount to recipient
+        let new_recipient_balance = match recipient_balance.checked_add(lock.get_amount()) {
+            Some(v) => v,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: ClaimWithPreimage. Locked amount exceeds the maximum balance of recipient.",
+                )))
+            }
+        };
+        state.set_balance(recipient_pubkey, new_recipient_balance)?;
+        state.delete_hashlock(lock.get_hashlock())?;
+
+        Ok(())
+    }
+
+    fn _refund_after_timeout(
+        &self,
+        payload: payload::RefundAfterTimeoutAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let lock = match state.get_hashlock(payload.get_hashlock())? {
+            Some(lock) => lock,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: RefundAfterTimeout. Hash lock doesn't exist.",
+                )))
+            }
+        };
+        //Lock can only be refunded once its timeout has passed
+        if timestamp < lock.get_timeout() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: RefundAfterTimeout. Hash lock has not expired.",
+            )))
+        }
+
+        let sender_pubkey = lock.get_sender_pubkey();
+        //Get sender balance
+        let sender_balance: u32 = match state.get_balance(sender_pubkey) {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                info!("Creating new account for user.");
+                0
+            }
+            Err(err) => return Err(err),
+        };
+
+        //Return locked amount to sender
+        let new_sender_balance = match sender_balance.checked_add(lock.get_amount()) {
+            Some(v) => v,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: RefundAfterTimeout. Locked amount exceeds the maximum balance of sender.",
+                )))
+            }
+        };
+        state.set_balance(sender_pubkey, new_sender_balance)?;
+        state.delete_hashlock(lock.get_hashlock())?;
+
+        Ok(())
+    }
+
+    fn _create_multisig(
+        &self,
+        payload: payload::CreateMultisigAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let account_id = payload.get_account_id();
+        if account_id.is_empty() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateMultisig. Account id is required.",
+            )))
+        }
+        //Account id should not be a public key, whose balance belongs to its key holder
+        if (account_id.len() == 66 || account_id.len() == 130) && account_id.chars().all(|c| c.is_digit(16)) {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateMultisig. Account id cannot be a public key.",
+            )))
+        }
+        //Members should be distinct, non empty public keys
+        let member_pubkeys = payload.get_member_pubkeys();
+        let mut distinct_pubkeys = member_pubkeys.to_vec();
+        distinct_pubkeys.sort();
+        distinct_pubkeys.dedup();
+        if member_pubkeys.is_empty()
+            || distinct_pubkeys.len() != member_pubkeys.len()
+            || member_pubkeys.iter().any(|pubkey| pubkey.is_empty())
+        {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateMultisig. Members must be distinct public keys.",
+            )))
+        }
+        //Only a member can create the account
+        if !member_pubkeys.iter().any(|pubkey| pubkey == signer) {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateMultisig. Signer is not a member of the account.",
+            )))
+        }
+        //Threshold should be between 1 and the number of members
+        let threshold = payload.get_threshold();
+        if threshold == 0 || threshold as usize > member_pubkeys.len() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateMultisig. Threshold is out of range.",
+            )))
+        }
+        //Account id should not be taken by another account
+        if state.get_multisig(account_id)?.is_some() || state.get_balance(account_id)?.is_some() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateMultisig. Account already exists.",
+            )))
+        }
+
+        //Store new multisig account to state
+        let mut account = MultisigAccount::new();
+        account.set_account_id(account_id.to_string());
+        account.set_member_pubkeys(RepeatedField::from_vec(member_pubkeys.to_vec()));
+        account.set_threshold(threshold);
+        state.set_multisig(account)?;
+
+        Ok(())
+    }
+
+    fn _propose_multisig_transfer(
+        &self,
+        payload: payload::ProposeMultisigTransferAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let account = match state.get_multisig(payload.get_account_id())? {
+            Some(account) => account,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: ProposeMultisigTransfer. Multisig account doesn't exist.",
+                )))
+            }
+        };
+        //Only members can propose
+        if !account.get_member_pubkeys().iter().any(|pubkey| pubkey == signer) {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ProposeMultisigTransfer. Signer is not a member of the account.",
+            )))
+        }
+        let proposal_id = payload.get_proposal_id();
+        if proposal_id.is_empty() || state.get_multisig_proposal(account.get_account_id(), proposal_id)?.is_some() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ProposeMultisigTransfer. Proposal id is empty or already in use.",
+            )))
+        }
+        if payload.get_amount() == 0 {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ProposeMultisigTransfer. Amount must be greater than zero.",
+            )))
+        }
+
+        //The proposer approves its own proposal
+        let mut proposal = MultisigProposal::new();
+        proposal.set_account_id(account.get_account_id().to_string());
+        proposal.set_proposal_id(proposal_id.to_string());
+        proposal.set_beneficiary_pubkey(payload.get_beneficiary_pubkey().to_string());
+        proposal.set_amount(payload.get_amount());
+        proposal.mut_approvals().push(signer.to_string());
+        if proposal.get_approvals().len() >= account.get_threshold() as usize {
+            self._execute_multisig_proposal(&mut state, &mut proposal, timestamp)?;
+        }
+        state.set_multisig_proposal(proposal)?;
+
+        Ok(())
+    }
+
+    fn _approve_multisig_transfer(
+        &self,
+        payload: payload::ApproveMultisigTransferAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let account = match state.get_multisig(payload.get_account_id())? {
+            Some(account) => account,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: ApproveMultisigTransfer. Multisig account doesn't exist.",
+                )))
+            }
+        };
+        let mut proposal = match state.get_multisig_proposal(account.get_account_id(), payload.get_proposal_id())? {
+            Some(proposal) => proposal,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: ApproveMultisigTransfer. Proposal doesn't exist.",
+                )))
+            }
+        };
+        if proposal.get_account_id() != account.get_account_id() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ApproveMultisigTransfer. Proposal belongs to another account.",
+            )))
+        }
+        if proposal.get_executed() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ApproveMultisigTransfer. Proposal has already been executed.",
+            )))
+        }
+        //Only members can approve, and only once
+        if !account.get_member_pubkeys().iter().any(|pubkey| pubkey == signer) {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ApproveMultisigTransfer. Signer is not a member of the account.",
+            )))
+        }
+        if proposal.get_approvals().iter().any(|pubkey| pubkey == signer) {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ApproveMultisigTransfer. Signer has already approved the proposal.",
+            )))
+        }
+
+        proposal.mut_approvals().push(signer.to_string());
+        if proposal.get_approvals().len() >= account.get_threshold() as usize {
+            self._execute_multisig_proposal(&mut state, &mut proposal, timestamp)?;
+        }
+        state.set_multisig_proposal(proposal)?;
+
+        Ok(())
+    }
+
+    fn _execute_multisig_proposal(
+        &self,
+        state: &mut DGCState,
+        proposal: &mut MultisigProposal,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let account_id = proposal.get_account_id().to_string();
+        self._check_not_frozen(state, &account_id)?;
+        //Get balance of multisig account
+        let account_balance: u32 = match state.get_balance(&account_id) {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                info!("Creating new account for user.");
+                0
+            }
+            Err(err) => return Err(err),
+        };
+
+        let beneficiary_pubkey = proposal.get_beneficiary_pubkey().to_string();
+        //Get beneficiary balance
+        let beneficiary_balance: u32 = match state.get_balance(&beneficiary_pubkey) {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                info!("Creating new account for user.");
+                0
+            }
+            Err(err) => return Err(err),
+        };
+
+        let amount = proposal.get_amount();
+        //Proposal amount should not be greater than current account balance
+        if amount > account_balance {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: Multisig transfer amount is more than account balance.",
+            )))
+        }
+        let new_beneficiary_balance = match beneficiary_balance.checked_add(amount) {
+            Some(v) => v,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: Multisig transfer amount exceeds the maximum balance of beneficiary.",
+                )))
+            }
+        };
+
+        //Outflow should stay within the spending limits of the account
+        self._record_outflow(state, &account_id, amount, timestamp)?;
+
+        //Store new balance to state
+        state.set_balance(&account_id, account_balance - amount)?;
+        state.set_balance(&beneficiary_pubkey, new_beneficiary_balance)?;
+        proposal.set_executed(true);
+
+        Ok(())
+    }
+
+    fn _create_vesting_grant(
+        &self,
+        payload: payload::CreateVestingGrantAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let grant_id = payload.get_grant_id();
+        let beneficiary_pubkey = payload.get_beneficiary_pubkey();
+        if grant_id.is_empty() || beneficiary_pubkey.is_empty() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateVestingGrant. Grant id and beneficiary are required.",
+            )))
+        }
+        //Schedule should satisfy start <= cliff <= end with start < end
+        let (start, cliff, end) = (payload.get_start(), payload.get_cliff(), payload.get_end());
+        if start >= end || cliff < start || cliff > end {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateVestingGrant. Invalid vesting schedule.",
+            )))
+        }
+        if state.get_vesting_grant(beneficiary_pubkey, grant_id)?.is_some() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateVestingGrant. Grant already exists.",
+            )))
+        }
+        //Multisig accounts can only be spent through approved proposals
+        if state.get_multisig(signer)?.is_some() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateVestingGrant. Multisig account requires an approved proposal.",
+            )))
+        }
+        self._check_not_frozen(&mut state, signer)?;
+        //Get balance of grantor
+        let grantor_balance: u32 = match state.get_balance(signer) {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                info!("Creating new account for user.");
+                0
+            }
+            Err(err) => return Err(err),
+        };
+
+        let amount = payload.get_amount();
+        //Grant amount should not be greater than current account balance
+        if amount == 0 || amount > grantor_balance {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateVestingGrant. Grant amount is zero or more than grantor account balance.",
+            )))
+        }
+
+        //Outflow should stay within the spending limits of the grantor
+        self._record_outflow(&mut state, signer, amount, timestamp)?;
+
+        //Move amount from grantor balance into the grant
+        let mut grant = VestingGrant::new();
+        grant.set_grant_id(grant_id.to_string());
+        grant.set_grantor_pubkey(signer.to_string());
+        grant.set_beneficiary_pubkey(beneficiary_pubkey.to_string());
+        grant.set_amount(amount);
+        grant.set_start(start);
+        grant.set_cliff(cliff);
+        grant.set_end(end);
+        state.set_balance(signer, grantor_balance - amount)?;
+        state.set_vesting_grant(grant)?;
+
+        Ok(())
+    }
+
+    fn _claim_vested(
+        &self,
+        payload: payload::ClaimVestedAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let mut grant = match state.get_vesting_grant(signer, payload.get_grant_id())? {
+            Some(grant) => grant,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: ClaimVested. Grant doesn't exist.",
+                )))
+            }
+        };
+        //A grant of another beneficiary may hash to the same address
+        if grant.get_beneficiary_pubkey() != signer {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ClaimVested. Signer is not the beneficiary of the grant.",
+            )))
+        }
+
+        //Nothing vests before the cliff, everything has vested at the end
+        let amount = grant.get_amount();
+        let vested_amount: u32 = if timestamp < grant.get_cliff() {
+            0
+        } else if timestamp >= grant.get_end() {
+            amount
+        } else {
+            let elapsed = timestamp - grant.get_start();
+            let duration = grant.get_end() - grant.get_start();
+            (u64::from(amount) * elapsed / duration) as u32
+        };
+
+        //Timestamps are set by the client and may be earlier than a previous claim
+        if vested_amount <= grant.get_claimed_amount() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ClaimVested. No vested amount to claim.",
+            )))
+        }
+        let claimable_amount = vested_amount - grant.get_claimed_amount();
+
+        //Get beneficiary balance
+        let beneficiary_balance: u32 = match state.get_balance(signer) {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                info!("Creating new account for user.");
+                0
+            }
+            Err(err) => return Err(err),
+        };
+
+        //Release claimable amount to beneficiary
+        let new_beneficiary_balance = match beneficiary_balance.checked_add(claimable_amount) {
+            Some(v) => v,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: ClaimVested. Claimable amount exceeds the maximum balance of beneficiary.",
+                )))
+            }
+        };
+        grant.set_claimed_amount(vested_amount);
+        state.set_balance(signer, new_beneficiary_balance)?;
+        state.set_vesting_grant(grant)?;
+
+        Ok(())
+    }
+
+    fn _create_standing_order(
+        &self,
+        payload: payload::CreateStandingOrderAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let order_id = payload.get_order_id();
+        let payee_pubkey = payload.get_payee_pubkey();
+        if order_id.is_empty() || payee_pubkey.is_empty() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateStandingOrder. Order id and payee are required.",
+            )))
+        }
+        if payload.get_amount() == 0 || payload.get_period() == 0 {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateStandingOrder. Amount and period must be greater than zero.",
+            )))
+        }
+        if payload.get_cap() != 0 && payload.get_cap() < payload.get_amount() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateStandingOrder. Cap is less than one payment.",
+            )))
+        }
+        //Payments cannot be backdated
+        if payload.get_start() < timestamp {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateStandingOrder. Start is in the past.",
+            )))
+        }
+        if state.get_standing_order(signer, order_id)?.is_some() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CreateStandingOrder. Order already exists.",
+            )))
+        }
+
+        //Store new standing order to state, first payment is due at start
+        let mut order = StandingOrder::new();
+        order.set_order_id(order_id.to_string());
+        order.set_payer_pubkey(signer.to_string());
+        order.set_payee_pubkey(payee_pubkey.to_string());
+        order.set_amount(payload.get_amount());
+        order.set_period(payload.get_period());
+        order.set_cap(payload.get_cap());
+        order.set_next_execution(payload.get_start());
+        state.set_standing_order(order)?;
+
+        Ok(())
+    }
+
+    fn _execute_standing_order(
+        &self,
+        payload: payload::ExecuteStandingOrderAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let mut order = match state.get_standing_order(payload.get_payer_pubkey(), payload.get_order_id())? {
+            Some(order) => order,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: ExecuteStandingOrder. Order doesn't exist.",
+                )))
+            }
+        };
+        if order.get_cancelled() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ExecuteStandingOrder. Order has been cancelled.",
+            )))
+        }
+        //Only one payment per elapsed period
+        if timestamp < order.get_next_execution() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ExecuteStandingOrder. Payment period has not elapsed.",
+            )))
+        }
+        let amount = order.get_amount();
+        let overflow = || ApplyError::InvalidTransaction(String::from(
+            "Action: ExecuteStandingOrder. Payment exceeds the maximum amount of payee or order.",
+        ));
+        let total_paid = order.get_total_paid().checked_add(amount).ok_or_else(overflow)?;
+        if order.get_cap() != 0 && total_paid > order.get_cap() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ExecuteStandingOrder. Payment would exceed the order cap.",
+            )))
+        }
+
+        let payer_pubkey = order.get_payer_pubkey().to_string();
+        //Multisig accounts can only be spent through approved proposals
+        if state.get_multisig(&payer_pubkey)?.is_some() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ExecuteStandingOrder. Multisig account requires an approved proposal.",
+            )))
+        }
+        self._check_not_frozen(&mut state, &payer_pubkey)?;
+        //Get balance of payer
+        let payer_balance: u32 = match state.get_balance(&payer_pubkey) {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                info!("Creating new account for user.");
+                0
+            }
+            Err(err) => return Err(err),
+        };
+        //Payment should not be greater than current account balance
+        if amount > payer_balance {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ExecuteStandingOrder. Payment is more than payer account balance.",
+            )))
+        }
+
+        let payee_pubkey = order.get_payee_pubkey().to_string();
+        //Get payee balance
+        let payee_balance: u32 = match state.get_balance(&payee_pubkey) {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                info!("Creating new account for user.");
+                0
+            }
+            Err(err) => return Err(err),
+        };
+
+        let new_payee_balance = payee_balance.checked_add(amount).ok_or_else(overflow)?;
+        //Move to the first period after now, so periods missed while no one
+        //executed the order are not paid later
+        let period = order.get_period();
+        let next_execution = ((timestamp - order.get_next_execution()) / period + 1)
+            .checked_mul(period)
+            .and_then(|elapsed| order.get_next_execution().checked_add(elapsed))
+            .ok_or_else(overflow)?;
+
+        //Outflow should stay within the spending limits of the payer
+        self._record_outflow(&mut state, &payer_pubkey, amount, timestamp)?;
+
+        //Store new balances and order to state
+        state.set_balance(&payer_pubkey, payer_balance - amount)?;
+        state.set_balance(&payee_pubkey, new_payee_balance)?;
+        order.set_next_execution(next_execution);
+        order.set_total_paid(total_paid);
+        state.set_standing_order(order)?;
+
+        Ok(())
+    }
+
+    fn _cancel_standing_order(
+        &self,
+        payload: payload::CancelStandingOrderAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        let mut order = match state.get_standing_order(payload.get_payer_pubkey(), payload.get_order_id())? {
+            Some(order) => order,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: CancelStandingOrder. Order doesn't exist.",
+                )))
+            }
+        };
+        //Only the payer or payee can cancel
+        if signer != order.get_payer_pubkey() && signer != order.get_payee_pubkey() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CancelStandingOrder. Signer is not the payer or payee.",
+            )))
+        }
+        if order.get_cancelled() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: CancelStandingOrder. Order has already been cancelled.",
+            )))
+        }
+
+        order.set_cancelled(true);
+        state.set_standing_order(order)?;
+
+        Ok(())
+    }
+
+    fn _freeze_account(
+        &self,
+        payload: payload::FreezeAccountAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        if !self._is_allowed_key(&mut state, COMPLIANCE_KEYS_SETTING, signer)? {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: FreezeAccount. Signer is not a compliance key.",
+            )))
+        }
+        let account_pubkey = payload.get_account_pubkey();
+        let mut account = match state.get_account(account_pubkey)? {
+            Some(account) => account,
+            None => Account::new(),
+        };
+        if account.get_frozen() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: FreezeAccount. Account is already frozen.",
+            )))
+        }
+
+        account.set_frozen(true);
+        state.set_account(account_pubkey, account)?;
+
+        Ok(())
+    }
+
+    fn _unfreeze_account(
+        &self,
+        payload: payload::UnfreezeAccountAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        if !self._is_allowed_key(&mut state, COMPLIANCE_KEYS_SETTING, signer)? {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: UnfreezeAccount. Signer is not a compliance key.",
+            )))
+        }
+        let account_pubkey = payload.get_account_pubkey();
+        let mut account = match state.get_account(account_pubkey)? {
+            Some(ref account) if account.get_frozen() => account.clone(),
+            _ => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: UnfreezeAccount. Account is not frozen.",
+                )))
+            }
+        };
+
+        account.set_frozen(false);
+        state.set_account(account_pubkey, account)?;
+
+        Ok(())
+    }
+
+    fn _attest_kyc(
+        &self,
+        payload: payload::AttestKycAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        if !self._is_allowed_key(&mut state, KYC_VERIFIER_KEYS_SETTING, signer)? {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: AttestKyc. Signer is not a KYC verifier.",
+            )))
+        }
+        let subject_pubkey = payload.get_subject_pubkey();
+        if subject_pubkey.is_empty() {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: AttestKyc. Subject is required.",
+            )))
+        }
+
+        //Store new attestation to state, replacing any previous one
+        let mut attestation = KycAttestation::new();
+        attestation.set_subject_pubkey(subject_pubkey.to_string());
+        attestation.set_tier(payload.get_tier());
+        attestation.set_expiry(payload.get_expiry());
+        attestation.set_authority_pubkey(signer.to_string());
+        state.set_kyc(attestation)?;
+
+        Ok(())
+    }
+
+    fn _set_spending_limits(
+        &self,
+        payload: payload::SetSpendingLimitsAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        if !self._is_allowed_key(&mut state, COMPLIANCE_KEYS_SETTING, signer)? {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: SetSpendingLimits. Signer is not a compliance key.",
+            )))
+        }
+        let account_pubkey = payload.get_account_pubkey();
+        let mut account = match state.get_account(account_pubkey)? {
+            Some(account) => account,
+            None => Account::new(),
+        };
+
+        account.set_max_transfer(payload.get_max_transfer());
+        account.set_daily_outflow_limit(payload.get_daily_outflow_limit());
+        state.set_account(account_pubkey, account)?;
+
+        Ok(())
+    }
+
+    fn _update_exchange_rate(
+        &self,
+        payload: payload::UpdateExchangeRateAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        if !self._is_allowed_key(&mut state, ORACLE_KEYS_SETTING, signer)? {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: UpdateExchangeRate. Signer is not an oracle.",
+            )))
+        }
+        if payload.get_rate() == 0 {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: UpdateExchangeRate. Rate must be greater than zero.",
+            )))
+        }
+
+        let currency = payload.get_currency();
+        let mut report = PriceReport::new();
+        report.set_currency(currency.to_string());
+        report.set_oracle_pubkey(signer.to_string());
+        report.set_rate(payload.get_rate());
+        report.set_timestamp(timestamp);
+        state.set_price_report(report)?;
+
+        //Publish the new median once enough oracles have reported; a median
+        //far from the previous one halts trading
+        let rates = self._get_fresh_rates(&mut state, currency, timestamp)?;
+        if !rates.is_empty() && rates.len() >= self._get_oracle_quorum(&mut state)? {
+            if let Some(previous) = state.get_exchange(currency)? {
+                self._trip_circuit_breaker(&mut state, currency, u64::from(median(&rates)), 1, previous.get_rate(), timestamp)?;
+            }
+            let mut exchange_rate = ExchangeRate::new();
+            exchange_rate.set_currency(currency.to_string());
+            exchange_rate.set_rate(median(&rates));
+            exchange_rate.set_timestamp(timestamp);
+            exchange_rate.set_report_count(rates.len() as u32);
+            state.set_exchange(exchange_rate)?;
+        }
+
+        Ok(())
+    }
+
+    fn _resume_trading(
+        &self,
+        payload: payload::ResumeTradingAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        if !self._is_allowed_key(&mut state, ADMIN_KEYS_SETTING, signer)? {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: ResumeTrading. Signer is not an admin key.",
+            )))
+        }
+        let mut halt = match state.get_trading_halt(payload.get_currency())? {
+            Some(ref halt) if halt.get_halted() => halt.clone(),
+            _ => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: ResumeTrading. Trading is not halted.",
+                )))
+            }
+        };
+
+        halt.set_halted(false);
+        state.set_trading_halt(halt)?;
+
+        Ok(())
+    }
+
+    fn _pause(
+        &self,
+        payload: payload::PauseAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        if !self._is_allowed_key(&mut state, ADMIN_KEYS_SETTING, signer)? {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: Pause. Signer is not an admin key.",
+            )))
+        }
+        let mut pause = state.get_pause()?.unwrap_or_else(PauseState::new);
+        if payload.get_actions().is_empty() {
+            pause.set_paused(true);
+        }
+        for action in payload.get_actions() {
+            //Action types are named as in the payload, e.g. SELL_DG_COIN
+            if !payload::DGCPayload_Action::values().iter().any(|action_type| &format!("{:?}", action_type) == action) {
+                return Err(ApplyError::InvalidTransaction(format!(
+                    "Action: Pause. Unknown action type {}.",
+                    action
+                )))
+            }
+            if !pause.get_paused_actions().contains(action) {
+                pause.mut_paused_actions().push(action.to_string());
+            }
+        }
+        pause.set_timestamp(timestamp);
+        pause.set_admin_pubkey(signer.to_string());
+        state.set_pause(pause)?;
+
+        Ok(())
+    }
+
+    fn _unpause(
+        &self,
+        payload: payload::UnpauseAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        if !self._is_allowed_key(&mut state, ADMIN_KEYS_SETTING, signer)? {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: Unpause. Signer is not an admin key.",
+            )))
+        }
+        let mut pause = match state.get_pause()? {
+            Some(pause) => pause,
+            None => {
+                return Err(ApplyError::InvalidTransaction(String::from(
+                    "Action: Unpause. Processor is not paused.",
+                )))
+            }
+        };
+        if payload.get_actions().is_empty() {
+            pause.set_paused(false);
+            pause.clear_paused_actions();
+        } else {
+            let paused_actions: Vec<String> = pause
+                .get_paused_actions()
+                .iter()
+                .filter(|action| !payload.get_actions().contains(*action))
+                .cloned()
+                .collect();
+            pause.set_paused_actions(RepeatedField::from_vec(paused_actions));
+        }
+        pause.set_timestamp(timestamp);
+        pause.set_admin_pubkey(signer.to_string());
+        state.set_pause(pause)?;
+
+        Ok(())
+    }
+
+    fn _migrate_accounts(
+        &self,
+        payload: payload::MigrateAccountsAction,
+        mut state: DGCState,
+        signer: &str,
+        timestamp: u64,
+    ) -> Result<(), ApplyError> {
+        if !self._is_allowed_key(&mut state, ADMIN_KEYS_SETTING, signer)? {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: MigrateAccounts. Signer is not an admin key.",
+            )))
+        }
+        let balance_prefix = get_balance_prefix();
+        let mut migrated = 0;
+        for address in payload.get_addresses() {
+            if address.len() != 70 || !address.starts_with(&balance_prefix) {
+                return Err(ApplyError::InvalidTransaction(format!(
+                    "Action: MigrateAccounts. {} is not a balance address.",
+                    address
+                )))
+            }
+            if state.migrate_account(address)? {
+                migrated += 1;
+            }
+        }
+        if migrated == 0 {
+            return Err(ApplyError::InvalidTransaction(String::from(
+                "Action: MigrateAccounts. No legacy balances to migrate.",
+            )))
+        }
+        info!("Migrated {} legacy balances.", migrated);
+
+        Ok(())
+    }
+
+    //Debits are refused from frozen accounts
+    fn _check_not_frozen(&self, state: &mut DGCState, pubkey: &str) -> Result<(), ApplyError> {
+        match state.get_account(pubkey)? {
+            Some(ref account) if account.get_frozen() => Err(ApplyError::InvalidTransaction(format!(
+                "Account {} is frozen.",
+                pubkey
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    //Moving an asset requires an unexpired attestation of at least its minimum tier
+    fn _check_kyc_tier(&self, state: &mut DGCState, asset: &str, pubkey: &str, timestamp: u64) -> Result<(), ApplyError> {
+        let min_tier: u32 = match state.get_setting(&format!("{}.{}", KYC_MIN_TIER_SETTING, asset))? {
+            Some(v) => match v.trim().parse() {
+                Ok(tier) => tier,
+                Err(_) => return Err(ApplyError::InvalidTransaction(format!("Invalid KYC tier setting for {}", asset))),
+            },
+            None => 0,
+        };
+        if min_tier == 0 {
+            return Ok(())
+        }
+        let tier = match state.get_kyc(pubkey)? {
+            Some(ref attestation) if attestation.get_expiry() > timestamp => attestation.get_tier(),
+            _ => 0,
+        };
+        if tier < min_tier {
+            return Err(ApplyError::InvalidTransaction(format!(
+                "Account {} lacks KYC tier {} required for {}.",
+                pubkey, min_tier, asset
+            )))
+        }
+        Ok(())
+    }
+
+    //Pool fee in basis points of the amount in, kept in the pool
+    fn _get_pool_fee(&self, state: &mut DGCState) -> Result<u32, ApplyError> {
+        match state.get_setting(POOL_FEE_SETTING)? {
+            Some(v) => match v.trim().parse() {
+                Ok(basis_points) if basis_points <= 10000 => Ok(basis_points),
+                _ => Err(ApplyError::InvalidTransaction(String::from("Invalid pool fee setting"))),
+            },
+            None => Ok(DEFAULT_POOL_FEE),
+        }
+    }
+
+    fn _check_not_paused(&self, state: &mut DGCState, action_type: payload::DGCPayload_Action) -> Result<(), ApplyError> {
+        let pause = match state.get_pause()? {
+            Some(pause) => pause,
+            None => return Ok(()),
+        };
+        let action_type = format!("{:?}", action_type);
+        if pause.get_paused() || pause.get_paused_actions().contains(&action_type) {
+            return Err(ApplyError::InvalidTransaction(format!(
+                "Processor is paused for {}.",
+                action_type
+            )))
+        }
+        Ok(())
+    }
+
+    fn _check_not_halted(&self, state: &mut DGCState, currency: &str) -> Result<(), ApplyError> {
+        match state.get_trading_halt(currency)? {
+            Some(ref halt) if halt.get_halted() => Err(ApplyError::InvalidTransaction(format!(
+                "Trading of {} is halted.",
+                currency
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    //Halts trading of the currency when the price of currency_amount for
+    //dgc_amount deviates from the reference by more than the configured
+    //percentage, returning whether it did
+    fn _trip_circuit_breaker(
+        &self,
+        state: &mut DGCState,
+        currency: &str,
+        currency_amount: u64,
+        dgc_amount: u64,
+        reference_price: u32,
+        timestamp: u64,
+    ) -> Result<bool, ApplyError> {
+        let max_deviation = match state.get_setting(&format!("{}.{}", MAX_DEVIATION_SETTING, currency))? {
+            Some(v) => Some(v),
+            None => state.get_setting(MAX_DEVIATION_SETTING)?,
+        };
+        let max_deviation: u64 = match max_deviation {
+            Some(v) => match v.trim().parse() {
+                Ok(percentage) => percentage,
+                Err(_) => return Err(ApplyError::InvalidTransaction(format!("Invalid circuit breaker setting for {}", currency))),
+            },
+            None => return Ok(false),
+        };
+        //Compared cross-multiplied so small amounts are not truncated to a price
+        let reference_amount = u128::from(reference_price) * u128::from(dgc_amount);
+        let deviation = if u128::from(currency_amount) > reference_amount {
+            u128::from(currency_amount) - reference_amount
+        } else {
+            reference_amount - u128::from(currency_amount)
+        };
+        if reference_price == 0 || deviation * 100 <= reference_amount * u128::from(max_deviation) {
+            return Ok(false)
+        }
+        let price = (currency_amount / dgc_amount.max(1)).min(u64::from(u32::max_value())) as u32;
+
+        warn!("Circuit breaker of {} tripped at {} against {}.", currency, price, reference_price);
+        let mut halt = TradingHalt::new();
+        halt.set_currency(currency.to_string());
+        halt.set_halted(true);
+        halt.set_price(price);
+        halt.set_reference_price(reference_price);
+        halt.set_timestamp(timestamp);
+        state.set_trading_halt(halt)?;
+        state.add_event(
+            "dgc-core/circuit-breaker",
+            vec![
+                (String::from("currency"), currency.to_string()),
+                (String::from("price"), price.to_string()),
+                (String::from("reference_price"), reference_price.to_string()),
+            ],
+            &[],
+        )?;
+        Ok(true)
+    }
+
+    //Exchange rate of a currency in currency units per DGC: the median of the
+    //fresh oracle reports when they reach the quorum, else the TWAP of trades
+    //when a TWAP window is set, else the last traded price, 0 when none exists
+    fn _get_exchange_rate(&self, state: &mut DGCState, currency: &str, timestamp: u64) -> Result<u32, ApplyError> {
+        let rates = self._get_fresh_rates(state, currency, timestamp)?;
+        if !rates.is_empty() && rates.len() >= self._get_oracle_quorum(state)? {
+            return Ok(median(&rates))
+        }
+        if let Some(twap_window) = self._get_seconds_setting(state, TWAP_WINDOW_SETTING)? {
+            if let Some(twap) = self._get_twap(state, currency, twap_window, timestamp)? {
+                return Ok(twap)
+            }
         }
-        
-        //Get exchange rate of currency
-        let exchange_rate: u32 = match state.get_exchange(currency) {
-            Ok(Some(v)) => v,
-            Ok(None) => {
+        let exchange_rate: u32 = match state.get_trade_summary(currency)? {
+            Some(summary) => summary.get_last_price(),
+            None => {
                 info!("Creating new exchange rate for currency.");
-                0              
+                0
             }
-            Err(err) => return Err(err),
         };
+        Ok(exchange_rate)
+    }
 
-        //Get open for buy of currency
-        let open_for_buy: u32 = match state.get_buy(currency, exchange_rate) {
-            Ok(Some(v)) => v,
-            Ok(None) => {
-                info!("Creating new sell for currency.");
-                0              
-            }
-            Err(err) => return Err(err),
+    //Rates of the reports of the current oracle keys that are not stale
+    fn _get_fresh_rates(&self, state: &mut DGCState, currency: &str, timestamp: u64) -> Result<Vec<u32>, ApplyError> {
+        let oracle_keys = match state.get_setting(ORACLE_KEYS_SETTING)? {
+            Some(v) => v,
+            None => return Ok(Vec::new()),
         };
+        let max_age = self._get_seconds_setting(state, ORACLE_MAX_AGE_SETTING)?.unwrap_or(DEFAULT_ORACLE_MAX_AGE);
+        let mut rates = Vec::new();
+        for oracle in oracle_keys.split(',').map(|key| key.trim()).filter(|key| !key.is_empty()) {
+            match state.get_price_report(currency, oracle)? {
+                //Reports of another currency or oracle may hash to the same address
+                Some(ref report)
+                    if report.get_currency() == currency
+                        && report.get_oracle_pubkey() == oracle
+                        && timestamp.saturating_sub(report.get_timestamp()) < max_age =>
+                {
+                    rates.push(report.get_rate())
+                }
+                _ => (),
+            }
+        }
+        Ok(rates)
+    }
 
+    fn _get_oracle_quorum(&self, state: &mut DGCState) -> Result<usize, ApplyError> {
+        match state.get_setting(ORACLE_QUORUM_SETTING)? {
+            Some(v) => match v.trim().parse() {
+                Ok(quorum) => Ok(quorum),
+                Err(_) => Err(ApplyError::InvalidTransaction(String::from("Invalid oracle quorum setting"))),
+            },
+            None => Ok(DEFAULT_ORACLE_QUORUM),
+        }
+    }
 
+    fn _get_seconds_setting(&self, state: &mut DGCState, setting: &str) -> Result<Option<u64>, ApplyError> {
+        match state.get_setting(setting)? {
+            Some(v) => match v.trim().parse() {
+                Ok(seconds) => Ok(Some(seconds)),
+                Err(_) => Err(ApplyError::InvalidTransaction(format!("Invalid {} setting", setting))),
+            },
+            None => Ok(None),
+        }
+    }
 
-        let beneficiary_pubkey = payload.get_beneficiary_pubkey();
-        //Get beneficiary balance
-        let beneficiary_balance: u32 = match state.get_balance(beneficiary_pubkey) {
-            Ok(Some(v)) => v,
-            Ok(None) => {
-                info!("Creating new account for user.");
-                0              
+    //Time-weighted average trade price over the window ending at the timestamp,
+    //None before the first trade
+    fn _get_twap(&self, state: &mut DGCState, currency: &str, window: u64, timestamp: u64) -> Result<Option<u32>, ApplyError> {
+        let accumulator = match state.get_twap(currency)? {
+            Some(accumulator) => accumulator,
+            None => return Ok(None),
+        };
+        let now = timestamp.max(accumulator.get_last_timestamp());
+        let cumulative_price = accumulator
+            .get_cumulative_price()
+            .wrapping_add(u64::from(accumulator.get_last_price()) * (now - accumulator.get_last_timestamp()));
+        //Latest observation at or before the start of the window, else the oldest
+        let window_start = now.saturating_sub(window);
+        let observations = accumulator.get_observations();
+        let start = observations
+            .iter()
+            .rev()
+            .find(|observation| observation.get_timestamp() <= window_start)
+            .or_else(|| observations.first());
+        match start {
+            Some(observation) if observation.get_timestamp() < now => {
+                let elapsed = now - observation.get_timestamp();
+                Ok(Some((cumulative_price.wrapping_sub(observation.get_cumulative_price()) / elapsed) as u32))
             }
-            Err(err) => return Err(err),
+            _ => Ok(Some(accumulator.get_last_price())),
+        }
+    }
+
+    //Adds the time the previous trade price was held to the accumulator and
+    //observes the new price; only kept while a TWAP window is set
+    fn _accumulate_price(&self, state: &mut DGCState, currency: &str, price: u32, timestamp: u64) -> Result<(), ApplyError> {
+        let window = match self._get_seconds_setting(state, TWAP_WINDOW_SETTING)? {
+            Some(window) => window,
+            None => return Ok(()),
         };
-        //Get credit of beneficiary
-        let beneficiary_credit: u32 = match state.get_credit(beneficiary_pubkey, currency) {
-            Ok(Some(v)) => v,
-            Ok(None) => {
-                info!("Creating new currency for user.");
-                0              
+        let mut accumulator = match state.get_twap(currency)? {
+            Some(accumulator) => accumulator,
+            None => {
+                let mut accumulator = PriceAccumulator::new();
+                accumulator.set_currency(currency.to_string());
+                accumulator.set_last_timestamp(timestamp);
+                accumulator
             }
-            Err(err) => return Err(err),
-        };                                
-        
-        let expected_sell_currency_amount = payload.get_expected_sell_currency_amount();
-        //expected sell currency amount should not be greater than beneficiary credit
-        if expected_sell_currency_amount < beneficiary_credit {
-            return Err(ApplyError::InvalidTransaction(String::from(
-                "Action: Transfer amount is more than customer account balance.",
-            )))
+        };
+        let now = timestamp.max(accumulator.get_last_timestamp());
+        let cumulative_price = accumulator
+            .get_cumulative_price()
+            .wrapping_add(u64::from(accumulator.get_last_price()) * (now - accumulator.get_last_timestamp()));
+        accumulator.set_cumulative_price(cumulative_price);
+        accumulator.set_last_price(price);
+        accumulator.set_last_timestamp(now);
+
+        //Observations older than the one opening the window are dropped
+        let window_start = now.saturating_sub(window);
+        let mut observations: Vec<PriceAccumulator_Observation> = accumulator
+            .get_observations()
+            .iter()
+            .filter(|observation| observation.get_timestamp() != now)
+            .cloned()
+            .collect();
+        while observations.len() > 1 && observations[1].get_timestamp() <= window_start {
+            observations.remove(0);
         }
-        
-        //Store new balance to state
-        //state.set_balance(customer_pubkey, current_balance - transfer_amount)?;
-        //state.set_balance(beneficiary_pubkey, beneficiary_balance + transfer_amount)?;
-                                     
-        Ok(())    
+        let mut observation = PriceAccumulator_Observation::new();
+        observation.set_timestamp(now);
+        observation.set_cumulative_price(cumulative_price);
+        observations.push(observation);
+        accumulator.set_observations(RepeatedField::from_vec(observations));
+        state.set_twap(accumulator)
     }
 
-    fn _buy_dg_coin(
+    //Resting orders of one side to fill against, walking the order book index
+    //best price first and oldest first within a price, with the quantity taken
+    //from each. Expired orders are removed from the book on the way.
+    fn _match_orders(
         &self,
-        payload: handler::payload::DGCPayload::SellDGCoinAction,
-        mut state: DGCState,
-        signer: &str,
+        state: &mut DGCState,
+        side: payload::OrderSide,
+        currency: &str,
+        limit_price: Option<u32>,
+        quantity: u32,
+        taker_pubkey: &str,
         timestamp: u64,
-    ) -> Result<(), ApplyError> {
-        let currency = payload.get_currency();
-        let customer_pubkey = payload.get_customer_pubkey();
-        //Get balance of customer
-        let customer_balance: u32 = match state.get_balance(customer_pubkey) {
-            Ok(Some(v)) => v,
-            Ok(None) => {
-                info!("Creating new account for user.");
-                0              
+    ) -> Result<Vec<(Order, u32)>, ApplyError> {
+        let mut fills: Vec<(Order, u32)> = Vec::new();
+        let mut remaining = quantity;
+        let book = self._get_book(state, side, currency)?;
+        for level in book.get_levels() {
+            let crosses = match (limit_price, side) {
+                (None, _) => true,
+                (Some(price), payload::OrderSide::SELL) => level.get_price() <= price,
+                (Some(price), payload::OrderSide::BUY) => level.get_price() >= price,
+            };
+            if remaining == 0 || !crosses {
+                break;
             }
-            Err(err) => return Err(err),
-        };        
-        //Get credit of customer
-        let customer_credit: u32 = match state.get_credit(customer_pubkey, "DGC") {
-            Ok(Some(v)) => v,
-            Ok(None) => {
-                info!("Creating new currency for user.");
-                0              
+            for order_id in level.get_order_ids() {
+                if remaining == 0 {
+                    break;
+                }
+                let order = match side {
+                    payload::OrderSide::SELL => state.get_sell(currency, order_id)?,
+                    payload::OrderSide::BUY => state.get_buy(currency, order_id)?,
+                };
+                let order = match order {
+                    Some(order) => order,
+                    None => {
+                        return Err(ApplyError::InvalidTransaction(format!(
+                            "Order {} is in the book but not in state",
+                            order_id
+                        )))
+                    }
+                };
+                if is_expired(&order, timestamp) {
+                    self._release_order(state, side, order)?;
+                    continue;
+                }
+                //Orders of the taker are left on the book instead of filled against itself
+                if order.get_customer_pubkey() == taker_pubkey {
+                    continue;
+                }
+                let fill_quantity = remaining.min(order.get_quantity());
+                remaining -= fill_quantity;
+                fills.push((order, fill_quantity));
             }
-            Err(err) => return Err(err),
-        };                                
-                                
-        let sell_amount = payload.get_sell_amount();
-        //Sell amount should not be greater than current account balance + customer_credit
-        if sell_amount > customer_balance + customer_credit{
-            return Err(ApplyError::InvalidTransaction(String::from(
-                "Action: Transfer amount is more than customer account balance.",
-            )))
         }
-        
-        //Get exchange rate of currency
-        let exchange_rate: u32 = match state.get_exchange(currency) {
-            Ok(Some(v)) => v,
-            Ok(None) => {
-                info!("Creating new exchange rate for currency.");
-                0              
+        Ok(fills)
+    }
+
+    //Settles the maker side of a fill out of its reserve, records the trade
+    //and returns the currency amount of the fill
+    fn _fill_order(
+        &self,
+        state: &mut DGCState,
+        side: payload::OrderSide,
+        currency: &str,
+        mut order: Order,
+        quantity: u32,
+        taker_pubkey: &str,
+        timestamp: u64,
+    ) -> Result<u64, ApplyError> {
+        self._record_trade(state, &order, quantity, taker_pubkey, timestamp)?;
+        let amount = u64::from(quantity) * u64::from(order.get_price());
+        let beneficiary_pubkey = order.get_beneficiary_pubkey().to_string();
+        let reserved = order.get_reserved();
+        let remaining = order.get_quantity() - quantity;
+        match side {
+            //Sell orders reserve DGC and receive currency
+            payload::OrderSide::SELL => {
+                order.set_reserved(reserved - quantity);
+                let beneficiary_credit = state.get_credit(&beneficiary_pubkey, currency)?.unwrap_or(0);
+                let new_beneficiary_credit = u64::from(beneficiary_credit) + amount;
+                if new_beneficiary_credit > u64::from(u32::max_value()) {
+                    return Err(ApplyError::InvalidTransaction(format!(
+                        "Fill of order {} exceeds the maximum credit of its beneficiary.",
+                        order.get_order_id()
+                    )))
+                }
+                state.set_credit(&beneficiary_pubkey, currency, new_beneficiary_credit as u32)?;
+            }
+            //Buy orders reserve currency and receive DGC
+            payload::OrderSide::BUY => {
+                order.set_reserved(reserved - amount as u32);
+                let beneficiary_balance = state.get_balance(&beneficiary_pubkey)?.unwrap_or(0);
+                let new_beneficiary_balance = match beneficiary_balance.checked_add(quantity) {
+                    Some(v) => v,
+                    None => {
+                        return Err(ApplyError::InvalidTransaction(format!(
+                            "Fill of order {} exceeds the maximum balance of its beneficiary.",
+                            order.get_order_id()
+                        )))
+                    }
+                };
+                state.set_balance(&beneficiary_pubkey, new_beneficiary_balance)?;
+            }
+        }
+        order.set_quantity(remaining);
+        if remaining == 0 {
+            self._release_order(state, side, order)?;
+        } else {
+            match side {
+                payload::OrderSide::SELL => state.set_sell(order)?,
+                payload::OrderSide::BUY => state.set_buy(order)?,
+            }
+        }
+        Ok(amount)
+    }
+
+    //Writes the trade record of a fill and rolls it into the currency summary
+    fn _record_trade(&self, state: &mut DGCState, order: &Order, quantity: u32, taker_pubkey: &str, timestamp: u64) -> Result<(), ApplyError> {
+        let currency = order.get_currency();
+        let mut trade = Trade::new();
+        trade.set_trade_id(format!("{}:{}", timestamp, order.get_order_id()));
+        trade.set_currency(currency.to_string());
+        trade.set_price(order.get_price());
+        trade.set_quantity(quantity);
+        trade.set_maker_pubkey(order.get_customer_pubkey().to_string());
+        trade.set_taker_pubkey(taker_pubkey.to_string());
+        trade.set_timestamp(timestamp);
+        state.set_trade(trade)?;
+
+        let mut summary = match state.get_trade_summary(currency)? {
+            Some(summary) => summary,
+            None => {
+                let mut summary = TradeSummary::new();
+                summary.set_currency(currency.to_string());
+                summary
             }
-            Err(err) => return Err(err),
         };
+        //Only hourly volumes of the last 24 hours are kept
+        let bucket_start = timestamp - timestamp % VOLUME_BUCKET;
+        let mut volumes: Vec<TradeSummary_Volume> = summary
+            .get_volumes()
+            .iter()
+            .filter(|volume| volume.get_start() + VOLUME_WINDOW > timestamp)
+            .cloned()
+            .collect();
+        match volumes.iter_mut().find(|volume| volume.get_start() == bucket_start) {
+            Some(volume) => {
+                let bucket_quantity = volume.get_quantity() + u64::from(quantity);
+                volume.set_quantity(bucket_quantity);
+            }
+            None => {
+                let mut volume = TradeSummary_Volume::new();
+                volume.set_start(bucket_start);
+                volume.set_quantity(u64::from(quantity));
+                volumes.push(volume);
+            }
+        }
+        let volume_24h = volumes.iter().fold(0, |total, volume| total + volume.get_quantity());
+        summary.set_last_price(order.get_price());
+        summary.set_last_timestamp(timestamp);
+        summary.set_volume_24h(volume_24h);
+        summary.set_volumes(RepeatedField::from_vec(volumes));
+        state.set_trade_summary(summary)?;
 
-        //Get open for buy of currency
-        let open_for_buy: u32 = match state.get_buy(currency, exchange_rate) {
-            Ok(Some(v)) => v,
-            Ok(None) => {
-                info!("Creating new sell for currency.");
-                0              
+        self._accumulate_price(state, currency, order.get_price(), timestamp)
+    }
+
+    //Removes an order from the book and returns what is left of its reserve
+    fn _release_order(&self, state: &mut DGCState, side: payload::OrderSide, order: Order) -> Result<(), ApplyError> {
+        let customer_pubkey = order.get_customer_pubkey();
+        let currency = order.get_currency();
+        let overflow = || ApplyError::InvalidTransaction(String::from(
+            "Order reserve exceeds the maximum amount of customer.",
+        ));
+        self._remove_from_book(state, side, &order)?;
+        match side {
+            payload::OrderSide::SELL => {
+                if order.get_reserved() > 0 {
+                    let customer_balance = state.get_balance(customer_pubkey)?.unwrap_or(0);
+                    let customer_balance = customer_balance.checked_add(order.get_reserved()).ok_or_else(overflow)?;
+                    state.set_balance(customer_pubkey, customer_balance)?;
+                }
+                state.delete_sell(currency, order.get_order_id())
             }
-            Err(err) => return Err(err),
+            payload::OrderSide::BUY => {
+                if order.get_reserved() > 0 {
+                    let customer_credit = state.get_credit(customer_pubkey, currency)?.unwrap_or(0);
+                    let customer_credit = customer_credit.checked_add(order.get_reserved()).ok_or_else(overflow)?;
+                    state.set_credit(customer_pubkey, currency, customer_credit)?;
+                }
+                state.delete_buy(currency, order.get_order_id())
+            }
+        }
+    }
+
+    fn _get_book(&self, state: &mut DGCState, side: payload::OrderSide, currency: &str) -> Result<OrderBook, ApplyError> {
+        let book = match side {
+            payload::OrderSide::SELL => state.get_sell_book(currency)?,
+            payload::OrderSide::BUY => state.get_buy_book(currency)?,
         };
+        Ok(book.unwrap_or_else(OrderBook::new))
+    }
 
+    fn _set_book(&self, state: &mut DGCState, side: payload::OrderSide, currency: &str, book: OrderBook) -> Result<(), ApplyError> {
+        match side {
+            payload::OrderSide::SELL => state.set_sell_book(currency, book),
+            payload::OrderSide::BUY => state.set_buy_book(currency, book),
+        }
+    }
 
+    //Adds a resting order to the end of its price level; sell levels ascend
+    //and buy levels descend in price so the best price comes first
+    fn _add_to_book(&self, state: &mut DGCState, side: payload::OrderSide, order: &Order) -> Result<(), ApplyError> {
+        let currency = order.get_currency();
+        let price = order.get_price();
+        let mut book = self._get_book(state, side, currency)?;
+        let book_size = book.get_levels().iter().fold(0, |size, level| size + level.get_order_ids().len());
+        if book_size >= MAX_ORDER_BOOK_SIZE {
+            //A full book makes room for a better price by releasing the
+            //newest order of its worst level
+            let worst_level = book.get_levels().last().cloned().unwrap_or_else(OrderBook_PriceLevel::new);
+            let is_better = match side {
+                payload::OrderSide::SELL => price < worst_level.get_price(),
+                payload::OrderSide::BUY => price > worst_level.get_price(),
+            };
+            let evicted_id = match worst_level.get_order_ids().last() {
+                Some(order_id) if is_better => Some(order_id.clone()),
+                _ => None,
+            };
+            let evicted = match evicted_id {
+                Some(order_id) => match side {
+                    payload::OrderSide::SELL => state.get_sell(currency, &order_id)?,
+                    payload::OrderSide::BUY => state.get_buy(currency, &order_id)?,
+                },
+                None => None,
+            };
+            match evicted {
+                Some(evicted) => {
+                    self._release_order(state, side, evicted)?;
+                    book = self._get_book(state, side, currency)?;
+                }
+                None => {
+                    return Err(ApplyError::InvalidTransaction(format!(
+                        "Order book of {} is full.",
+                        currency
+                    )))
+                }
+            }
+        }
 
-        let beneficiary_pubkey = payload.get_beneficiary_pubkey();
-        //Get beneficiary balance
-        let beneficiary_balance: u32 = match state.get_balance(beneficiary_pubkey) {
-            Ok(Some(v)) => v,
-            Ok(None) => {
-                info!("Creating new account for user.");
-                0              
+        let position = book.get_levels().iter().position(|level| match side {
+            payload::OrderSide::SELL => level.get_price() >= price,
+            payload::OrderSide::BUY => level.get_price() <= price,
+        });
+        match position {
+            Some(i) if book.get_levels()[i].get_price() == price => {
+                book.mut_levels()[i].mut_order_ids().push(order.get_order_id().to_string());
             }
-            Err(err) => return Err(err),
-        };
-        //Get credit of beneficiary
-        let beneficiary_credit: u32 = match state.get_credit(beneficiary_pubkey, currency) {
-            Ok(Some(v)) => v,
-            Ok(None) => {
-                info!("Creating new currency for user.");
-                0              
+            _ => {
+                let mut level = OrderBook_PriceLevel::new();
+                level.set_price(price);
+                level.mut_order_ids().push(order.get_order_id().to_string());
+                let i = position.unwrap_or_else(|| book.get_levels().len());
+                book.mut_levels().insert(i, level);
             }
-            Err(err) => return Err(err),
-        };                                
-        
-        let expected_sell_currency_amount = payload.get_expected_sell_currency_amount();
-        //expected sell currency amount should not be greater than beneficiary credit
-        if expected_sell_currency_amount < beneficiary_credit {
+        }
+        self._set_book(state, side, currency, book)
+    }
+
+    //Removes an order from its price level, dropping the level once empty
+    fn _remove_from_book(&self, state: &mut DGCState, side: payload::OrderSide, order: &Order) -> Result<(), ApplyError> {
+        let currency = order.get_currency();
+        let mut book = self._get_book(state, side, currency)?;
+        let levels: Vec<OrderBook_PriceLevel> = book
+            .get_levels()
+            .iter()
+            .map(|level| {
+                let mut level = level.clone();
+                let order_ids: Vec<String> = level
+                    .get_order_ids()
+                    .iter()
+                    .filter(|order_id| order_id.as_str() != order.get_order_id())
+                    .cloned()
+                    .collect();
+                level.set_order_ids(RepeatedField::from_vec(order_ids));
+                level
+            })
+            .filter(|level| !level.get_order_ids().is_empty())
+            .collect();
+        book.set_levels(RepeatedField::from_vec(levels));
+        self._set_book(state, side, currency, book)
+    }
+
+    //Checks a debit against the account and global spending limits and adds
+    //it to the rolling outflow window of the account
+    fn _record_outflow(&self, state: &mut DGCState, pubkey: &str, amount: u32, timestamp: u64) -> Result<(), ApplyError> {
+        let mut account = match state.get_account(pubkey)? {
+            Some(account) => account,
+            None => Account::new(),
+        };
+        let max_transfer = self._get_limit(state, account.get_max_transfer(), LIMIT_MAX_TRANSFER_SETTING)?;
+        let daily_outflow_limit = self._get_limit(state, account.get_daily_outflow_limit(), LIMIT_DAILY_OUTFLOW_SETTING)?;
+
+        if max_transfer != 0 && amount > max_transfer {
+            return Err(ApplyError::InvalidTransaction(format!(
+                "Spending limit exceeded: {} is more than the maximum transfer of {} for account {}.",
+                amount, max_transfer, pubkey
+            )))
+        }
+
+        //Only outflows of the last 24 hours are kept
+        let recent_outflows: Vec<Outflow> = account
+            .get_outflows()
+            .iter()
+            .filter(|outflow| outflow.get_timestamp() + OUTFLOW_WINDOW > timestamp)
+            .cloned()
+            .collect();
+        let daily_outflow = recent_outflows
+            .iter()
+            .fold(u64::from(amount), |total, outflow| total + u64::from(outflow.get_amount()));
+        if daily_outflow_limit != 0 && daily_outflow > u64::from(daily_outflow_limit) {
+            return Err(ApplyError::InvalidTransaction(format!(
+                "Spending limit exceeded: 24 hour outflow of {} is more than the limit of {} for account {}.",
+                daily_outflow, daily_outflow_limit, pubkey
+            )))
+        }
+
+        let mut outflow = Outflow::new();
+        outflow.set_timestamp(timestamp);
+        outflow.set_amount(amount);
+        account.set_outflows(RepeatedField::from_vec(recent_outflows));
+        account.mut_outflows().push(outflow);
+        state.set_account(pubkey, account)
+    }
+
+    //The stricter of an account limit and a global limit setting, 0 for no limit
+    fn _get_limit(&self, state: &mut DGCState, account_limit: u32, setting: &str) -> Result<u32, ApplyError> {
+        let global_limit: u32 = match state.get_setting(setting)? {
+            Some(v) => match v.trim().parse() {
+                Ok(limit) => limit,
+                Err(_) => return Err(ApplyError::InvalidTransaction(format!("Invalid limit setting {}", setting))),
+            },
+            None => 0,
+        };
+        Ok(match (account_limit, global_limit) {
+            (0, limit) | (limit, 0) => limit,
+            (account_limit, global_limit) => account_limit.min(global_limit),
+        })
+    }
+
+    //Fee configured for an action and asset, 0 when no treasury is set
+    fn _get_fee(&self, state: &mut DGCState, action: &str, asset: &str, amount: u32) -> Result<u32, ApplyError> {
+        if state.get_setting(FEE_TREASURY_SETTING)?.is_none() {
+            return Ok(0)
+        }
+        let fee_model = match state.get_setting(&format!("{}.{}.{}", FEE_SETTING, action, asset))? {
+            Some(v) => v,
+            None => return Ok(0),
+        };
+        let terms: Vec<u64> = match fee_model.split(',').map(|term| term.trim().parse()).collect() {
+            Ok(terms) => terms,
+            Err(_) => return Err(ApplyError::InvalidTransaction(format!("Invalid fee setting for {} {}", action, asset))),
+        };
+        let (flat, basis_points) = match terms.as_slice() {
+            [flat, basis_points] => (*flat, *basis_points),
+            _ => return Err(ApplyError::InvalidTransaction(format!("Invalid fee setting for {} {}", action, asset))),
+        };
+        let fee = flat + u64::from(amount) * basis_points / 10000;
+        if fee > u64::from(u32::max_value()) {
             return Err(ApplyError::InvalidTransaction(String::from(
-                "Action: Transfer amount is more than customer account balance.",
+                "Fee exceeds the maximum amount.",
             )))
         }
-        
-        //Store new balance to state
-        //state.set_balance(customer_pubkey, current_balance - transfer_amount)?;
-        //state.set_balance(beneficiary_pubkey, beneficiary_balance + transfer_amount)?;
-                                     
-        Ok(())    
+        Ok(fee as u32)
+    }
+
+    //Credits a fee already debited from the payer to the treasury and reports it
+    fn _collect_fee(&self, state: &mut DGCState, action: &str, asset: &str, payer_pubkey: &str, fee: u32) -> Result<(), ApplyError> {
+        if fee == 0 {
+            return Ok(())
+        }
+        let treasury_pubkey = match state.get_setting(FEE_TREASURY_SETTING)? {
+            Some(v) => v.trim().to_string(),
+            None => return Err(ApplyError::InvalidTransaction(String::from("Fee treasury is not set"))),
+        };
+        let overflow = || ApplyError::InvalidTransaction(format!("Fee exceeds the maximum {} of the treasury", asset));
+        if asset == "DGC" {
+            let treasury_balance = state.get_balance(&treasury_pubkey)?.unwrap_or(0);
+            state.set_balance(&treasury_pubkey, treasury_balance.checked_add(fee).ok_or_else(overflow)?)?;
+        } else {
+            let treasury_credit = state.get_credit(&treasury_pubkey, asset)?.unwrap_or(0);
+            state.set_credit(&treasury_pubkey, asset, treasury_credit.checked_add(fee).ok_or_else(overflow)?)?;
+        }
+
+        let mut receipt = FeeReceipt::new();
+        receipt.set_action(action.to_string());
+        receipt.set_asset(asset.to_string());
+        receipt.set_payer_pubkey(payer_pubkey.to_string());
+        receipt.set_treasury_pubkey(treasury_pubkey.clone());
+        receipt.set_amount(fee);
+        let data = match receipt.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize fee receipt"))),
+        };
+        state.add_receipt_data(&data)?;
+        state.add_event(
+            "dgc-core/fee",
+            vec![
+                (String::from("action"), action.to_string()),
+                (String::from("asset"), asset.to_string()),
+                (String::from("payer"), payer_pubkey.to_string()),
+                (String::from("treasury"), treasury_pubkey),
+            ],
+            &data,
+        )
+    }
+
+    //Whether signer is listed in a comma separated keys setting
+    fn _is_allowed_key(&self, state: &mut DGCState, setting: &str, signer: &str) -> Result<bool, ApplyError> {
+        let allowed_keys = match state.get_setting(setting)? {
+            Some(v) => v,
+            None => return Ok(false),
+        };
+        Ok(allowed_keys.split(',').any(|key| key.trim() == signer))
+    }
+
+}
+
+//Orders with an expiry stop filling once the payload timestamp reaches it
+fn is_expired(order: &Order, timestamp: u64) -> bool {
+    order.get_expiry() != 0 && order.get_expiry() <= timestamp
+}
+
+//Median of a non-empty list of rates, averaging the two middle rates when even
+fn median(rates: &[u32]) -> u32 {
+    let mut sorted = rates.to_vec();
+    sorted.sort();
+    let middle = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        ((u64::from(sorted[middle - 1]) + u64::from(sorted[middle])) / 2) as u32
+    } else {
+        sorted[middle]
+    }
+}
+
+//Integer square root, rounded down
+fn isqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n
+    }
+    let mut x = n;
+    let mut y = x / 2 + x % 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
     }
+    x
+}
 
+//Amount out of a constant-product pool for an amount in after the pool fee,
+//rounded down so the product of the reserves never decreases
+fn swap_output(amount_in: u32, reserve_in: u32, reserve_out: u32, fee_basis_points: u32) -> u32 {
+    let amount_in = u128::from(amount_in) * u128::from(10000 - fee_basis_points);
+    let numerator = amount_in * u128::from(reserve_out);
+    let denominator = u128::from(reserve_in) * 10000 + amount_in;
+    (numerator / denominator) as u32
 }
 
 impl TransactionHandler for DGCTransactionHandler {
@@ -321,7 +2692,11 @@ impl TransactionHandler for DGCTransactionHandler {
         request: &TpProcessRequest,
         context: &mut TransactionContext,
     ) -> Result<(), ApplyError> {
-        let payload = DGCPayload::new(request.get_payload());
+        let payload = DGCPayload::new(
+            request.get_payload(),
+            request.get_header().get_family_version(),
+            request.get_header().get_signer_public_key(),
+        );
         let payload = match payload {
             Err(e) => return Err(e),
             Ok(payload) => payload,
@@ -336,7 +2711,15 @@ impl TransactionHandler for DGCTransactionHandler {
         };
 
         let signer = request.get_header().get_signer_public_key();
-        let state = DGCState::new(context);
+        let mut state = match request.get_header().get_family_version() {
+            FAMILY_VERSION_1 => DGCState::with_legacy_inputs(context, request.get_header().get_inputs()),
+            _ => DGCState::new(context),
+        };
+
+        //While paused only admin actions are accepted
+        if !payload.get_action().is_admin() {
+            self._check_not_paused(&mut state, payload.get_action_type())?;
+        }
 
         info!(
             "payload: {:?} {} {} {}",
@@ -354,12 +2737,93 @@ impl TransactionHandler for DGCTransactionHandler {
             Action::TransferDGCoin(transfer_dg_coin_payload) => {
                 self._transfer_dg_coin(transfer_dg_coin_payload, state, signer, payload.get_timestamp())?
             }
+            Action::WithdrawDGCoin(withdraw_dg_coin_payload) => {
+                self._withdraw_dg_coin(withdraw_dg_coin_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::DGCBalance(dgc_balance_payload) => {
+                self._dgc_balance(dgc_balance_payload, state, signer, payload.get_timestamp())?
+            }
             Action::SellDGCoin(sell_dg_coin_payload) => {
                 self._sell_dg_coin(sell_dg_coin_payload, state, signer, payload.get_timestamp())?
             }
             Action::BuyDGCoin(buy_dg_coin_payload) => {
                 self._buy_dg_coin(buy_dg_coin_payload, state, signer, payload.get_timestamp())?
             }
+            Action::CancelOrder(cancel_order_payload) => {
+                self._cancel_order(cancel_order_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::PurgeExpiredOrders(purge_expired_orders_payload) => {
+                self._purge_expired_orders(purge_expired_orders_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::LockWithHash(lock_with_hash_payload) => {
+                self._lock_with_hash(lock_with_hash_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::ClaimWithPreimage(claim_with_preimage_payload) => {
+                self._claim_with_preimage(claim_with_preimage_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::RefundAfterTimeout(refund_after_timeout_payload) => {
+                self._refund_after_timeout(refund_after_timeout_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::CreateMultisig(create_multisig_payload) => {
+                self._create_multisig(create_multisig_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::ProposeMultisigTransfer(propose_multisig_transfer_payload) => {
+                self._propose_multisig_transfer(propose_multisig_transfer_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::ApproveMultisigTransfer(approve_multisig_transfer_payload) => {
+                self._approve_multisig_transfer(approve_multisig_transfer_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::CreateVestingGrant(create_vesting_grant_payload) => {
+                self._create_vesting_grant(create_vesting_grant_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::ClaimVested(claim_vested_payload) => {
+                self._claim_vested(claim_vested_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::CreateStandingOrder(create_standing_order_payload) => {
+                self._create_standing_order(create_standing_order_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::ExecuteStandingOrder(execute_standing_order_payload) => {
+                self._execute_standing_order(execute_standing_order_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::CancelStandingOrder(cancel_standing_order_payload) => {
+                self._cancel_standing_order(cancel_standing_order_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::FreezeAccount(freeze_account_payload) => {
+                self._freeze_account(freeze_account_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::UnfreezeAccount(unfreeze_account_payload) => {
+                self._unfreeze_account(unfreeze_account_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::AttestKyc(attest_kyc_payload) => {
+                self._attest_kyc(attest_kyc_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::SetSpendingLimits(set_spending_limits_payload) => {
+                self._set_spending_limits(set_spending_limits_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::AddLiquidity(add_liquidity_payload) => {
+                self._add_liquidity(add_liquidity_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::RemoveLiquidity(remove_liquidity_payload) => {
+                self._remove_liquidity(remove_liquidity_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::Swap(swap_payload) => {
+                self._swap(swap_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::UpdateExchangeRate(update_exchange_rate_payload) => {
+                self._update_exchange_rate(update_exchange_rate_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::ResumeTrading(resume_trading_payload) => {
+                self._resume_trading(resume_trading_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::Pause(pause_payload) => {
+                self._pause(pause_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::Unpause(unpause_payload) => {
+                self._unpause(unpause_payload, state, signer, payload.get_timestamp())?
+            }
+            Action::MigrateAccounts(migrate_accounts_payload) => {
+                self._migrate_accounts(migrate_accounts_payload, state, signer, payload.get_timestamp())?
+            }
 
         }
         Ok(())
@@ -782,3 +3246,130 @@ impl DGCTransactions for DGCTransactionHandler {
 }
 */
     
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use dgc_core_common::state::make_hashlock_state_address;
+    use handler::validator::Validator;
+
+    const SENDER: &str = "02a2a0b1e5a0a0c5e5a0a0b1e5a0a0c5e5a0a0b1e5a0a0c5e5a0a0b1e5a0a0c5e5";
+    const RECIPIENT: &str = "03b3b1c2f6b1b1d6f6b1b1c2f6b1b1d6f6b1b1c2f6b1b1d6f6b1b1c2f6b1b1d6f6";
+    const PREIMAGE: &str = "swap secret";
+    const LOCKED_AT: u64 = 1600000000;
+    const TIMEOUT: u64 = LOCKED_AT + 3600;
+
+    //Locks 40 of the 100 DGC of the sender for the recipient
+    fn locked() -> (DGCTransactionHandler, Validator) {
+        let handler = DGCTransactionHandler::new();
+        let mut validator = Validator::new();
+        validator.set_balance(SENDER, 100);
+        let mut action = payload::LockWithHashAction::new();
+        action.set_recipient_pubkey(RECIPIENT.to_string());
+        action.set_amount(40);
+        action.set_hashlock(hash(PREIMAGE, 128));
+        action.set_timeout(TIMEOUT);
+        validator.apply(&handler, SENDER, Action::LockWithHash(action), LOCKED_AT).unwrap();
+        assert_eq!(validator.get_balance(SENDER), 60);
+        (handler, validator)
+    }
+
+    fn claim(preimage: &str) -> Action {
+        let mut action = payload::ClaimWithPreimageAction::new();
+        action.set_hashlock(hash(PREIMAGE, 128));
+        action.set_preimage(preimage.to_string());
+        Action::ClaimWithPreimage(action)
+    }
+
+    fn refund() -> Action {
+        let mut action = payload::RefundAfterTimeoutAction::new();
+        action.set_hashlock(hash(PREIMAGE, 128));
+        Action::RefundAfterTimeout(action)
+    }
+
+    #[test]
+    fn claim_with_preimage() {
+        let (handler, mut validator) = locked();
+        validator.apply(&handler, RECIPIENT, claim(PREIMAGE), TIMEOUT - 1).unwrap();
+        assert_eq!(validator.get_balance(RECIPIENT), 40);
+        assert_eq!(validator.get_balance(SENDER), 60);
+        assert!(validator.get_state(&make_hashlock_state_address(&hash(PREIMAGE, 128))).is_none());
+    }
+
+    #[test]
+    fn claim_with_wrong_preimage() {
+        let (handler, mut validator) = locked();
+        assert!(validator.apply(&handler, RECIPIENT, claim("guess"), LOCKED_AT).is_err());
+        assert_eq!(validator.get_balance(RECIPIENT), 0);
+        assert!(validator.get_state(&make_hashlock_state_address(&hash(PREIMAGE, 128))).is_some());
+    }
+
+    #[test]
+    fn claim_only_once() {
+        let (handler, mut validator) = locked();
+        validator.apply(&handler, RECIPIENT, claim(PREIMAGE), LOCKED_AT).unwrap();
+        assert!(validator.apply(&handler, RECIPIENT, claim(PREIMAGE), LOCKED_AT + 1).is_err());
+        assert!(validator.apply(&handler, SENDER, refund(), TIMEOUT).is_err());
+        assert_eq!(validator.get_balance(RECIPIENT), 40);
+        assert_eq!(validator.get_balance(SENDER), 60);
+    }
+
+    #[test]
+    fn refund_before_timeout() {
+        let (handler, mut validator) = locked();
+        assert!(validator.apply(&handler, SENDER, refund(), TIMEOUT - 1).is_err());
+        assert_eq!(validator.get_balance(SENDER), 60);
+    }
+
+    #[test]
+    fn refund_after_timeout() {
+        let (handler, mut validator) = locked();
+        validator.apply(&handler, SENDER, refund(), TIMEOUT).unwrap();
+        assert_eq!(validator.get_balance(SENDER), 100);
+        //An expired lock can no longer be claimed
+        assert!(validator.apply(&handler, RECIPIENT, claim(PREIMAGE), TIMEOUT).is_err());
+        assert_eq!(validator.get_balance(RECIPIENT), 0);
+    }
+
+    #[test]
+    fn median_of_rates() {
+        assert_eq!(median(&[7]), 7);
+        assert_eq!(median(&[9, 1, 5]), 5);
+        assert_eq!(median(&[4, 1, 3, 2]), 2);
+        assert_eq!(median(&[u32::max_value(), u32::max_value()]), u32::max_value());
+    }
+
+    #[test]
+    fn isqrt_rounds_down() {
+        assert_eq!(isqrt(0), 0);
+        assert_eq!(isqrt(1), 1);
+        assert_eq!(isqrt(3), 1);
+        assert_eq!(isqrt(4), 2);
+        assert_eq!(isqrt(99), 9);
+        assert_eq!(isqrt(100), 10);
+        assert_eq!(isqrt(u64::max_value()), u64::from(u32::max_value()));
+    }
+
+    #[test]
+    fn swap_output_after_fee() {
+        assert_eq!(swap_output(100, 1000, 1000, 0), 90);
+        assert_eq!(swap_output(100, 1000, 1000, DEFAULT_POOL_FEE), 90);
+        assert_eq!(swap_output(1000, 1000, 1000, DEFAULT_POOL_FEE), 499);
+        assert_eq!(swap_output(0, 1000, 1000, DEFAULT_POOL_FEE), 0);
+        assert_eq!(swap_output(100, 1000, 1000, 10000), 0);
+    }
+
+    #[test]
+    fn swap_output_keeps_the_product() {
+        let (reserve_in, reserve_out) = (u32::max_value() / 3, 123457);
+        for &amount_in in &[1, 1000, 999999, u32::max_value() / 3] {
+            let amount_out = swap_output(amount_in, reserve_in, reserve_out, DEFAULT_POOL_FEE);
+            assert!(amount_out < reserve_out);
+            assert!(
+                u128::from(reserve_in + amount_in) * u128::from(reserve_out - amount_out)
+                    >= u128::from(reserve_in) * u128::from(reserve_out)
+            );
+        }
+    }
+}
@@ -2,5 +2,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod handler;
-mod payload;
 mod state;
+#[cfg(test)]
+mod validator;
@@ -1,100 +1,106 @@
 // Copyright (c) The dgc.network
 // SPDX-License-Identifier: Apache-2.0
 
-use crypto::digest::Digest;
-use crypto::sha2::Sha512;
+use protobuf;
+use protobuf::Message;
 
-use std::str;
 use std::collections::HashMap;
 
 use sawtooth_sdk::processor::handler::ApplyError;
 use sawtooth_sdk::processor::handler::TransactionContext;
+use sawtooth_sdk::messages::setting::Setting;
 
-const FAMILY_NAME: &str = "dgc-core";
-const FAMILY_VER: &str = "1.0";
-const DGC_BALANCE: &str = "balance";
-const DGC_EXCHANGE: &str= "exchange";
-const GET_CREDIT: &str  = "credit";
-const APPLY_CREDIT: &str = "apply";
-const TRANSFER_DGC: &str = "transfer";
-const SELL_DGC: &str = "sell";
-const BUY_DGC: &str = "buy";
-
-pub fn get_dgc_prefix() -> String {
-    let mut sha = Sha512::new();
-    sha.input_str(FAMILY_NAME);
-    sha.result_str()[..6].to_string()
-}
-
-pub fn hash(to_hash: &str, num: usize) -> String {
-    let mut sha = Sha512::new();
-    sha.input_str(to_hash);
-    let temp = sha.result_str().to_string();
-    let hash = match temp.get(..num) {
-        Some(x) => x,
-        None => "",
-    };
-    hash.to_string()
-}
-
-pub fn make_balance_state_address(identifier: &str) -> String {
-    hash(FAMILY_NAME, 6) + &hash(DGC_BALANCE, 2) + &hash(identifier, 62)
-}
-
-pub fn make_exchange_state_address(currency: &str) -> String {
-    hash(FAMILY_NAME, 6) + &hash(DGC_EXCHANGE, 2) + &hash(currency, 62)
-}
+use protos::state::Account;
+use protos::state::HashLock;
+use protos::state::MultisigAccount;
+use protos::state::MultisigProposal;
+use protos::state::VestingGrant;
+use protos::state::StandingOrder;
+use protos::state::KycAttestation;
+use protos::state::Order;
+use protos::state::OrderBook;
+use protos::state::LiquidityPool;
+use protos::state::LiquidityShare;
+use protos::state::Trade;
+use protos::state::TradeSummary;
+use protos::state::ExchangeRate;
+use protos::state::PriceAccumulator;
+use protos::state::PriceReport;
+use protos::state::TradingHalt;
+use protos::state::PauseState;
 
-pub fn make_credit_state_address(identifier: &str, currency: &str) -> String {
-    hash(FAMILY_NAME, 6) + &hash(GET_CREDIT, 2) + &hash(currency, 2) + &hash(identifier, 60)
-}
-
-pub fn make_sell_state_address(currency: &str, timestamp: &str) -> String {
-    hash(FAMILY_NAME, 6) + &hash(SELL_DGC, 2) + &hash(currency, 2) + &hash(timestamp, 60)
-}
-
-pub fn make_buy_state_address(currency: &str, timestamp: &str) -> String {
-    hash(FAMILY_NAME, 6) + &hash(BUY_DGC, 2) + &hash(currency, 2) + &hash(timestamp, 60)
-}
+use dgc_core_common::state::decode_account;
+use dgc_core_common::state::is_legacy_balance;
+use dgc_core_common::state::make_balance_state_address;
+use dgc_core_common::state::make_exchange_state_address;
+use dgc_core_common::state::make_credit_state_address;
+use dgc_core_common::state::make_sell_state_address;
+use dgc_core_common::state::make_buy_state_address;
+use dgc_core_common::state::make_sell_book_state_address;
+use dgc_core_common::state::make_buy_book_state_address;
+use dgc_core_common::state::make_pool_state_address;
+use dgc_core_common::state::make_pool_share_state_address;
+use dgc_core_common::state::make_trade_state_address;
+use dgc_core_common::state::make_trade_summary_state_address;
+use dgc_core_common::state::make_twap_state_address;
+use dgc_core_common::state::make_price_report_state_address;
+use dgc_core_common::state::make_circuit_breaker_state_address;
+use dgc_core_common::state::make_pause_state_address;
+use dgc_core_common::state::make_hashlock_state_address;
+use dgc_core_common::state::make_multisig_state_address;
+use dgc_core_common::state::make_multisig_proposal_state_address;
+use dgc_core_common::state::make_vesting_state_address;
+use dgc_core_common::state::make_standing_order_state_address;
+use dgc_core_common::state::make_kyc_state_address;
+use dgc_core_common::state::make_settings_state_address;
 
 //dgc-core State
 pub struct DGCState<'a> {
     context: &'a mut TransactionContext,
+    legacy_inputs: Option<Vec<String>>,
 }
 
 impl<'a> DGCState<'a> {
     pub fn new(context: &'a mut TransactionContext) -> DGCState {
         DGCState {
             context: context,
+            legacy_inputs: None,
         }
     }
 
-    pub fn get_balance(&mut self, identifier: &str) -> Result<Option<u32>, ApplyError> {
+    //Family version 1.0 clients only list the accounts they move as inputs.
+    //Settings, pause, KYC, multisig and trading halt state they do not list
+    //is read as unset, as it did not exist for them.
+    pub fn with_legacy_inputs(context: &'a mut TransactionContext, inputs: &[String]) -> DGCState<'a> {
+        DGCState {
+            context: context,
+            legacy_inputs: Some(inputs.to_vec()),
+        }
+    }
+
+    fn is_unlisted(&self, address: &str) -> bool {
+        match self.legacy_inputs {
+            Some(ref inputs) => !inputs.iter().any(|input| address.starts_with(input.as_str())),
+            None => false,
+        }
+    }
+
+    pub fn get_account(&mut self, identifier: &str) -> Result<Option<Account>, ApplyError> {
         let address = make_balance_state_address(identifier);
         let d = self.context.get_state(vec![address.clone()])?;
         match d {
-            Some(packed) => {                
-                
-                let value_string = match String::from_utf8(packed) {
-                    Ok(v) => v,
-                    Err(_) => return Err(ApplyError::InvalidTransaction(String::from("Invalid UTF-8 sequence")))
-                };                
-                
-                let value: u32 = match value_string.parse() {
-                    Ok(v) => v,
-                    Err(_) => return Err(ApplyError::InvalidTransaction(String::from("Unable to parse UTF-8 String as u32")))
-                };
-                
-                Ok(Some(value))
-                               
-            }
+            Some(packed) => Ok(Some(decode_account(&packed)?)),
             None => Ok(None),
         }
     }
 
-    pub fn set_balance(&mut self, identifier: &str, value: u32) -> Result<(), ApplyError> {       
+    pub fn set_account(&mut self, identifier: &str, account: Account) -> Result<(), ApplyError> {
+        let serialized = match account.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize account")))
+        };
         let mut sets = HashMap::new();
-        sets.insert(make_balance_state_address(identifier), value.to_string().into_bytes());
+        sets.insert(make_balance_state_address(identifier), serialized);
         self.context
             .set_state(sets)
             .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
@@ -102,32 +108,109 @@ impl<'a> DGCState<'a> {
         Ok(())
     }
 
-    pub fn get_exchange(&mut self, currency: &str) -> Result<Option<u32>, ApplyError> {
-        let address = make_exchange_state_address(currency);
+    //Rewrites a legacy balance entry at a balance address as an account,
+    //returning whether there was one
+    pub fn migrate_account(&mut self, address: &str) -> Result<bool, ApplyError> {
+        let packed = match self.context.get_state(vec![address.to_string()])? {
+            Some(packed) => packed,
+            None => return Ok(false),
+        };
+        if !is_legacy_balance(&packed) {
+            return Ok(false)
+        }
+        let serialized = match decode_account(&packed)?.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize account")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(address.to_string(), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(true)
+    }
+
+    pub fn get_balance(&mut self, identifier: &str) -> Result<Option<u32>, ApplyError> {
+        match self.get_account(identifier)? {
+            Some(account) => Ok(Some(account.get_balance())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_balance(&mut self, identifier: &str, value: u32) -> Result<(), ApplyError> {
+        //Keep the rest of the account record as it is
+        let mut account = match self.get_account(identifier)? {
+            Some(account) => account,
+            None => Account::new(),
+        };
+        account.set_balance(value);
+        self.set_account(identifier, account)
+    }
+
+    //Value of a key in the sawtooth settings namespace
+    pub fn get_setting(&mut self, key: &str) -> Result<Option<String>, ApplyError> {
+        let address = make_settings_state_address(key);
+        if self.is_unlisted(&address) {
+            return Ok(None)
+        }
         let d = self.context.get_state(vec![address.clone()])?;
         match d {
-            Some(packed) => {                
-                
-                let value_string = match String::from_utf8(packed) {
+            Some(packed) => {
+
+                let setting: Setting = match protobuf::parse_from_bytes(&packed) {
                     Ok(v) => v,
-                    Err(_) => return Err(ApplyError::InvalidTransaction(String::from("Invalid UTF-8 sequence")))
-                };                
-                
-                let value: u32 = match value_string.parse() {
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize setting")))
+                };
+
+                Ok(setting
+                    .get_entries()
+                    .iter()
+                    .find(|entry| entry.get_key() == key)
+                    .map(|entry| entry.get_value().to_string()))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn add_receipt_data(&mut self, data: &[u8]) -> Result<(), ApplyError> {
+        self.context
+            .add_receipt_data(data)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))
+    }
+
+    pub fn add_event(&mut self, event_type: &str, attributes: Vec<(String, String)>, data: &[u8]) -> Result<(), ApplyError> {
+        self.context
+            .add_event(event_type.to_string(), attributes, data)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))
+    }
+
+    pub fn get_exchange(&mut self, currency: &str) -> Result<Option<ExchangeRate>, ApplyError> {
+        let address = make_exchange_state_address(currency);
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let exchange_rate: ExchangeRate = match protobuf::parse_from_bytes(&packed) {
                     Ok(v) => v,
-                    Err(_) => return Err(ApplyError::InvalidTransaction(String::from("Unable to parse UTF-8 String as u32")))
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize exchange rate")))
                 };
-                
-                Ok(Some(value))
-                               
+
+                Ok(Some(exchange_rate))
+
             }
             None => Ok(None),
         }
     }
 
-    pub fn set_exchange(&mut self, currency: &str, value: u32) -> Result<(), ApplyError> {       
+    pub fn set_exchange(&mut self, exchange_rate: ExchangeRate) -> Result<(), ApplyError> {
+        let serialized = match exchange_rate.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize exchange rate")))
+        };
         let mut sets = HashMap::new();
-        sets.insert(make_exchange_state_address(currency), value.to_string().into_bytes());
+        sets.insert(make_exchange_state_address(exchange_rate.get_currency()), serialized);
         self.context
             .set_state(sets)
             .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
@@ -168,32 +251,71 @@ impl<'a> DGCState<'a> {
         Ok(())
     }
 
-    pub fn get_sell(&mut self, currency: &str, timestamp: &str) -> Result<Option<u32>, ApplyError> {
-        let address = make_sell_state_address(currency, timestamp);
+    pub fn get_sell(&mut self, currency: &str, order_id: &str) -> Result<Option<Order>, ApplyError> {
+        let address = make_sell_state_address(currency, order_id);
         let d = self.context.get_state(vec![address.clone()])?;
         match d {
-            Some(packed) => {                
-                
-                let value_string = match String::from_utf8(packed) {
+            Some(packed) => {
+
+                let order: Order = match protobuf::parse_from_bytes(&packed) {
                     Ok(v) => v,
-                    Err(_) => return Err(ApplyError::InvalidTransaction(String::from("Invalid UTF-8 sequence")))
-                };                
-                
-                let value: u32 = match value_string.parse() {
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize sell order")))
+                };
+
+                Ok(Some(order))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_sell(&mut self, order: Order) -> Result<(), ApplyError> {
+        let serialized = match order.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize sell order")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_sell_state_address(order.get_currency(), order.get_order_id()), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn delete_sell(&mut self, currency: &str, order_id: &str) -> Result<(), ApplyError> {
+        self.context
+            .delete_state(vec![make_sell_state_address(currency, order_id)])
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_buy(&mut self, currency: &str, order_id: &str) -> Result<Option<Order>, ApplyError> {
+        let address = make_buy_state_address(currency, order_id);
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let order: Order = match protobuf::parse_from_bytes(&packed) {
                     Ok(v) => v,
-                    Err(_) => return Err(ApplyError::InvalidTransaction(String::from("Unable to parse UTF-8 String as u32")))
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize buy order")))
                 };
-                
-                Ok(Some(value))
-                               
+
+                Ok(Some(order))
+
             }
             None => Ok(None),
         }
     }
 
-    pub fn set_sell(&mut self, currency: &str, timestamp: &str, value: u32) -> Result<(), ApplyError> {       
+    pub fn set_buy(&mut self, order: Order) -> Result<(), ApplyError> {
+        let serialized = match order.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize buy order")))
+        };
         let mut sets = HashMap::new();
-        sets.insert(make_sell_state_address(currency, timestamp), value.to_string().into_bytes());
+        sets.insert(make_buy_state_address(order.get_currency(), order.get_order_id()), serialized);
         self.context
             .set_state(sets)
             .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
@@ -201,32 +323,302 @@ impl<'a> DGCState<'a> {
         Ok(())
     }
 
-    pub fn get_buy(&mut self, currency: &str, timestamp: &str) -> Result<Option<u32>, ApplyError> {
-        let address = make_buy_state_address(currency, timestamp);
+    pub fn delete_buy(&mut self, currency: &str, order_id: &str) -> Result<(), ApplyError> {
+        self.context
+            .delete_state(vec![make_buy_state_address(currency, order_id)])
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_sell_book(&mut self, currency: &str) -> Result<Option<OrderBook>, ApplyError> {
+        let address = make_sell_book_state_address(currency);
         let d = self.context.get_state(vec![address.clone()])?;
         match d {
-            Some(packed) => {                
-                
-                let value_string = match String::from_utf8(packed) {
+            Some(packed) => {
+
+                let book: OrderBook = match protobuf::parse_from_bytes(&packed) {
                     Ok(v) => v,
-                    Err(_) => return Err(ApplyError::InvalidTransaction(String::from("Invalid UTF-8 sequence")))
-                };                
-                
-                let value: u32 = match value_string.parse() {
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize sell order book")))
+                };
+
+                Ok(Some(book))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_sell_book(&mut self, currency: &str, book: OrderBook) -> Result<(), ApplyError> {
+        let serialized = match book.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize sell order book")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_sell_book_state_address(currency), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_buy_book(&mut self, currency: &str) -> Result<Option<OrderBook>, ApplyError> {
+        let address = make_buy_book_state_address(currency);
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let book: OrderBook = match protobuf::parse_from_bytes(&packed) {
                     Ok(v) => v,
-                    Err(_) => return Err(ApplyError::InvalidTransaction(String::from("Unable to parse UTF-8 String as u32")))
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize buy order book")))
                 };
-                
-                Ok(Some(value))
-                               
+
+                Ok(Some(book))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_buy_book(&mut self, currency: &str, book: OrderBook) -> Result<(), ApplyError> {
+        let serialized = match book.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize buy order book")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_buy_book_state_address(currency), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_pool(&mut self, currency: &str) -> Result<Option<LiquidityPool>, ApplyError> {
+        let address = make_pool_state_address(currency);
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let pool: LiquidityPool = match protobuf::parse_from_bytes(&packed) {
+                    Ok(v) => v,
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize liquidity pool")))
+                };
+
+                Ok(Some(pool))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_pool(&mut self, pool: LiquidityPool) -> Result<(), ApplyError> {
+        let serialized = match pool.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize liquidity pool")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_pool_state_address(pool.get_currency()), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_pool_share(&mut self, currency: &str, owner: &str) -> Result<Option<LiquidityShare>, ApplyError> {
+        let address = make_pool_share_state_address(currency, owner);
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let share: LiquidityShare = match protobuf::parse_from_bytes(&packed) {
+                    Ok(v) => v,
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize liquidity share")))
+                };
+                //A share of another pool or owner hashed to the same address
+                if share.get_currency() != currency || share.get_owner_pubkey() != owner {
+                    return Err(ApplyError::InvalidTransaction(String::from("Liquidity share address is in use by another share")))
+                }
+
+                Ok(Some(share))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_pool_share(&mut self, share: LiquidityShare) -> Result<(), ApplyError> {
+        let serialized = match share.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize liquidity share")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_pool_share_state_address(share.get_currency(), share.get_owner_pubkey()), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_trade(&mut self, currency: &str, trade_id: &str) -> Result<Option<Trade>, ApplyError> {
+        let address = make_trade_state_address(currency, trade_id);
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let trade: Trade = match protobuf::parse_from_bytes(&packed) {
+                    Ok(v) => v,
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize trade")))
+                };
+
+                Ok(Some(trade))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_trade(&mut self, trade: Trade) -> Result<(), ApplyError> {
+        let serialized = match trade.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize trade")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_trade_state_address(trade.get_currency(), trade.get_trade_id()), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_trade_summary(&mut self, currency: &str) -> Result<Option<TradeSummary>, ApplyError> {
+        let address = make_trade_summary_state_address(currency);
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let summary: TradeSummary = match protobuf::parse_from_bytes(&packed) {
+                    Ok(v) => v,
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize trade summary")))
+                };
+
+                Ok(Some(summary))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_trade_summary(&mut self, summary: TradeSummary) -> Result<(), ApplyError> {
+        let serialized = match summary.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize trade summary")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_trade_summary_state_address(summary.get_currency()), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_twap(&mut self, currency: &str) -> Result<Option<PriceAccumulator>, ApplyError> {
+        let address = make_twap_state_address(currency);
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let accumulator: PriceAccumulator = match protobuf::parse_from_bytes(&packed) {
+                    Ok(v) => v,
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize price accumulator")))
+                };
+
+                Ok(Some(accumulator))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_twap(&mut self, accumulator: PriceAccumulator) -> Result<(), ApplyError> {
+        let serialized = match accumulator.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize price accumulator")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_twap_state_address(accumulator.get_currency()), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_price_report(&mut self, currency: &str, oracle: &str) -> Result<Option<PriceReport>, ApplyError> {
+        let address = make_price_report_state_address(currency, oracle);
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let report: PriceReport = match protobuf::parse_from_bytes(&packed) {
+                    Ok(v) => v,
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize price report")))
+                };
+
+                Ok(Some(report))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_price_report(&mut self, report: PriceReport) -> Result<(), ApplyError> {
+        let serialized = match report.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize price report")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_price_report_state_address(report.get_currency(), report.get_oracle_pubkey()), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_trading_halt(&mut self, currency: &str) -> Result<Option<TradingHalt>, ApplyError> {
+        let address = make_circuit_breaker_state_address(currency);
+        if self.is_unlisted(&address) {
+            return Ok(None)
+        }
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let halt: TradingHalt = match protobuf::parse_from_bytes(&packed) {
+                    Ok(v) => v,
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize trading halt")))
+                };
+
+                Ok(Some(halt))
+
             }
             None => Ok(None),
         }
     }
 
-    pub fn set_buy(&mut self, currency: &str, timestamp: &str, value: u32) -> Result<(), ApplyError> {       
+    pub fn set_trading_halt(&mut self, halt: TradingHalt) -> Result<(), ApplyError> {
+        let serialized = match halt.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize trading halt")))
+        };
         let mut sets = HashMap::new();
-        sets.insert(make_buy_state_address(currency, timestamp), value.to_string().into_bytes());
+        sets.insert(make_circuit_breaker_state_address(halt.get_currency()), serialized);
         self.context
             .set_state(sets)
             .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
@@ -234,4 +626,253 @@ impl<'a> DGCState<'a> {
         Ok(())
     }
 
-}    
\ No newline at end of file
+    pub fn get_pause(&mut self) -> Result<Option<PauseState>, ApplyError> {
+        let address = make_pause_state_address();
+        if self.is_unlisted(&address) {
+            return Ok(None)
+        }
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let pause: PauseState = match protobuf::parse_from_bytes(&packed) {
+                    Ok(v) => v,
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize pause state")))
+                };
+
+                Ok(Some(pause))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_pause(&mut self, pause: PauseState) -> Result<(), ApplyError> {
+        let serialized = match pause.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize pause state")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_pause_state_address(), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_hashlock(&mut self, hashlock: &str) -> Result<Option<HashLock>, ApplyError> {
+        let address = make_hashlock_state_address(hashlock);
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let lock: HashLock = match protobuf::parse_from_bytes(&packed) {
+                    Ok(v) => v,
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize hash lock")))
+                };
+
+                Ok(Some(lock))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_hashlock(&mut self, lock: HashLock) -> Result<(), ApplyError> {
+        let serialized = match lock.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize hash lock")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_hashlock_state_address(lock.get_hashlock()), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn delete_hashlock(&mut self, hashlock: &str) -> Result<(), ApplyError> {
+        self.context
+            .delete_state(vec![make_hashlock_state_address(hashlock)])
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_multisig(&mut self, account_id: &str) -> Result<Option<MultisigAccount>, ApplyError> {
+        let address = make_multisig_state_address(account_id);
+        if self.is_unlisted(&address) {
+            return Ok(None)
+        }
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let account: MultisigAccount = match protobuf::parse_from_bytes(&packed) {
+                    Ok(v) => v,
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize multisig account")))
+                };
+
+                Ok(Some(account))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_multisig(&mut self, account: MultisigAccount) -> Result<(), ApplyError> {
+        let serialized = match account.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize multisig account")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_multisig_state_address(account.get_account_id()), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_multisig_proposal(&mut self, account_id: &str, proposal_id: &str) -> Result<Option<MultisigProposal>, ApplyError> {
+        let address = make_multisig_proposal_state_address(account_id, proposal_id);
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let proposal: MultisigProposal = match protobuf::parse_from_bytes(&packed) {
+                    Ok(v) => v,
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize multisig proposal")))
+                };
+                //A proposal of another account hashed to the same address
+                if proposal.get_account_id() != account_id {
+                    return Err(ApplyError::InvalidTransaction(String::from("Multisig proposal address is in use by another account")))
+                }
+
+                Ok(Some(proposal))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_multisig_proposal(&mut self, proposal: MultisigProposal) -> Result<(), ApplyError> {
+        let serialized = match proposal.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize multisig proposal")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_multisig_proposal_state_address(proposal.get_account_id(), proposal.get_proposal_id()), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_vesting_grant(&mut self, beneficiary: &str, grant_id: &str) -> Result<Option<VestingGrant>, ApplyError> {
+        let address = make_vesting_state_address(beneficiary, grant_id);
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let grant: VestingGrant = match protobuf::parse_from_bytes(&packed) {
+                    Ok(v) => v,
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize vesting grant")))
+                };
+
+                Ok(Some(grant))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_vesting_grant(&mut self, grant: VestingGrant) -> Result<(), ApplyError> {
+        let serialized = match grant.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize vesting grant")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_vesting_state_address(grant.get_beneficiary_pubkey(), grant.get_grant_id()), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_standing_order(&mut self, payer: &str, order_id: &str) -> Result<Option<StandingOrder>, ApplyError> {
+        let address = make_standing_order_state_address(payer, order_id);
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let order: StandingOrder = match protobuf::parse_from_bytes(&packed) {
+                    Ok(v) => v,
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize standing order")))
+                };
+                //An order of another payer hashed to the same address
+                if order.get_payer_pubkey() != payer {
+                    return Err(ApplyError::InvalidTransaction(String::from("Standing order address is in use by another payer")))
+                }
+
+                Ok(Some(order))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_standing_order(&mut self, order: StandingOrder) -> Result<(), ApplyError> {
+        let serialized = match order.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize standing order")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_standing_order_state_address(order.get_payer_pubkey(), order.get_order_id()), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+    pub fn get_kyc(&mut self, identifier: &str) -> Result<Option<KycAttestation>, ApplyError> {
+        let address = make_kyc_state_address(identifier);
+        if self.is_unlisted(&address) {
+            return Ok(None)
+        }
+        let d = self.context.get_state(vec![address.clone()])?;
+        match d {
+            Some(packed) => {
+
+                let attestation: KycAttestation = match protobuf::parse_from_bytes(&packed) {
+                    Ok(v) => v,
+                    Err(_) => return Err(ApplyError::InternalError(String::from("Cannot deserialize KYC attestation")))
+                };
+
+                Ok(Some(attestation))
+
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_kyc(&mut self, attestation: KycAttestation) -> Result<(), ApplyError> {
+        let serialized = match attestation.write_to_bytes() {
+            Ok(v) => v,
+            Err(_) => return Err(ApplyError::InternalError(String::from("Cannot serialize KYC attestation")))
+        };
+        let mut sets = HashMap::new();
+        sets.insert(make_kyc_state_address(attestation.get_subject_pubkey()), serialized);
+        self.context
+            .set_state(sets)
+            .map_err(|err| ApplyError::InternalError(format!("{}", err)))?;
+
+        Ok(())
+    }
+
+}
@@ -0,0 +1,176 @@
+// Copyright (c) The dgc.network
+// SPDX-License-Identifier: Apache-2.0
+
+//In memory stand-in for the validator side of a transaction context, so
+//handler tests can apply transactions without a network
+
+use protobuf;
+use protobuf::Message;
+use protobuf::RepeatedField;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+use zmq;
+
+use sawtooth_sdk::messages::processor::TpProcessRequest;
+use sawtooth_sdk::messages::state_context::*;
+use sawtooth_sdk::messages::transaction::TransactionHeader;
+use sawtooth_sdk::messages::validator::Message as ValidatorMessage;
+use sawtooth_sdk::messages::validator::Message_MessageType;
+use sawtooth_sdk::messaging::stream::MessageConnection;
+use sawtooth_sdk::messaging::stream::MessageReceiver;
+use sawtooth_sdk::messaging::zmq_stream::ZmqMessageConnection;
+use sawtooth_sdk::processor::handler::ApplyError;
+use sawtooth_sdk::processor::handler::TransactionContext;
+use sawtooth_sdk::processor::handler::TransactionHandler;
+
+use dgc_core_common::payload::Action;
+use dgc_core_common::payload::FAMILY_VERSION_2;
+use dgc_core_common::state::decode_account;
+use dgc_core_common::state::get_dgc_prefix;
+use dgc_core_common::state::make_balance_state_address;
+
+use protos::state::Account;
+
+pub struct Validator {
+    state: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    context: TransactionContext,
+    _requests: MessageReceiver,
+}
+
+impl Validator {
+    pub fn new() -> Validator {
+        let zmq_context = zmq::Context::new();
+        let socket = zmq_context.socket(zmq::ROUTER).unwrap();
+        socket.bind("tcp://127.0.0.1:*").unwrap();
+        let endpoint = socket.get_last_endpoint().unwrap().unwrap();
+
+        let state = Arc::new(Mutex::new(HashMap::new()));
+        let served = state.clone();
+        thread::spawn(move || {
+            let _zmq_context = zmq_context;
+            loop {
+                serve(&socket, &served);
+            }
+        });
+
+        let (sender, requests) = ZmqMessageConnection::new(&endpoint).create();
+        Validator {
+            state: state,
+            context: TransactionContext::new("test", sender),
+            _requests: requests,
+        }
+    }
+
+    //Applies a version 2.0 transaction, discarding its changes when it is
+    //rejected as the validator would
+    pub fn apply<H: TransactionHandler>(&mut self, handler: &H, signer: &str, action: Action, timestamp: u64) -> Result<(), ApplyError> {
+        let mut header = TransactionHeader::new();
+        header.set_family_version(String::from(FAMILY_VERSION_2));
+        header.set_signer_public_key(signer.to_string());
+        header.set_inputs(RepeatedField::from_vec(vec![get_dgc_prefix()]));
+        header.set_outputs(RepeatedField::from_vec(vec![get_dgc_prefix()]));
+        let mut request = TpProcessRequest::new();
+        request.set_header(header);
+        request.set_payload(action.to_payload(timestamp).write_to_bytes().unwrap());
+
+        let snapshot = self.state.lock().unwrap().clone();
+        let result = handler.apply(&request, &mut self.context);
+        if result.is_err() {
+            *self.state.lock().unwrap() = snapshot;
+        }
+        result
+    }
+
+    pub fn get_state(&self, address: &str) -> Option<Vec<u8>> {
+        self.state.lock().unwrap().get(address).cloned()
+    }
+
+    pub fn get_balance(&self, pubkey: &str) -> u32 {
+        match self.get_state(&make_balance_state_address(pubkey)) {
+            Some(packed) => decode_account(&packed).unwrap().get_balance(),
+            None => 0,
+        }
+    }
+
+    pub fn set_balance(&self, pubkey: &str, balance: u32) {
+        let mut account = Account::new();
+        account.set_balance(balance);
+        self.state
+            .lock()
+            .unwrap()
+            .insert(make_balance_state_address(pubkey), account.write_to_bytes().unwrap());
+    }
+}
+
+//Answers one state, receipt or event request of the context
+fn serve(socket: &zmq::Socket, state: &Mutex<HashMap<String, Vec<u8>>>) {
+    let mut parts = socket.recv_multipart(0).unwrap();
+    let request: ValidatorMessage = protobuf::parse_from_bytes(&parts.pop().unwrap()).unwrap();
+    let content = request.get_content();
+    let mut state = state.lock().unwrap();
+
+    let (message_type, reply) = match request.get_message_type() {
+        Message_MessageType::TP_STATE_GET_REQUEST => {
+            let get: TpStateGetRequest = protobuf::parse_from_bytes(content).unwrap();
+            let entries = get
+                .get_addresses()
+                .iter()
+                .map(|address| {
+                    let mut entry = TpStateEntry::new();
+                    entry.set_address(address.clone());
+                    entry.set_data(state.get(address).cloned().unwrap_or_default());
+                    entry
+                })
+                .collect();
+            let mut response = TpStateGetResponse::new();
+            response.set_entries(RepeatedField::from_vec(entries));
+            response.set_status(TpStateGetResponse_Status::OK);
+            (Message_MessageType::TP_STATE_GET_RESPONSE, response.write_to_bytes().unwrap())
+        }
+        Message_MessageType::TP_STATE_SET_REQUEST => {
+            let set: TpStateSetRequest = protobuf::parse_from_bytes(content).unwrap();
+            let mut response = TpStateSetResponse::new();
+            for entry in set.get_entries() {
+                state.insert(entry.get_address().to_string(), entry.get_data().to_vec());
+                response.mut_addresses().push(entry.get_address().to_string());
+            }
+            response.set_status(TpStateSetResponse_Status::OK);
+            (Message_MessageType::TP_STATE_SET_RESPONSE, response.write_to_bytes().unwrap())
+        }
+        Message_MessageType::TP_STATE_DELETE_REQUEST => {
+            let delete: TpStateDeleteRequest = protobuf::parse_from_bytes(content).unwrap();
+            let mut response = TpStateDeleteResponse::new();
+            for address in delete.get_addresses() {
+                if state.remove(address).is_some() {
+                    response.mut_addresses().push(address.clone());
+                }
+            }
+            response.set_status(TpStateDeleteResponse_Status::OK);
+            (Message_MessageType::TP_STATE_DELETE_RESPONSE, response.write_to_bytes().unwrap())
+        }
+        Message_MessageType::TP_RECEIPT_ADD_DATA_REQUEST => {
+            let mut response = TpReceiptAddDataResponse::new();
+            response.set_status(TpReceiptAddDataResponse_Status::OK);
+            (Message_MessageType::TP_RECEIPT_ADD_DATA_RESPONSE, response.write_to_bytes().unwrap())
+        }
+        Message_MessageType::TP_EVENT_ADD_REQUEST => {
+            let mut response = TpEventAddResponse::new();
+            response.set_status(TpEventAddResponse_Status::OK);
+            (Message_MessageType::TP_EVENT_ADD_RESPONSE, response.write_to_bytes().unwrap())
+        }
+        message_type => panic!("Unexpected request {:?}", message_type),
+    };
+
+    let mut message = ValidatorMessage::new();
+    message.set_message_type(message_type);
+    message.set_correlation_id(request.get_correlation_id().to_string());
+    message.set_content(reply);
+    let message = message.write_to_bytes().unwrap();
+    let mut frames: Vec<&[u8]> = parts.iter().map(|part| part.as_slice()).collect();
+    frames.push(&message);
+    socket.send_multipart(&frames, 0).unwrap();
+}
@@ -4,15 +4,19 @@
 extern crate cbor;
 #[macro_use]
 extern crate clap;
-extern crate crypto;
+extern crate dgc_core_common;
 #[macro_use]
 extern crate log;
 extern crate protobuf;
 extern crate log4rs;
 extern crate sawtooth_sdk;
+#[cfg(test)]
+extern crate zmq;
 
 mod handler;
 
+use dgc_core_common::protos;
+
 use log::LevelFilter;
 use log4rs::append::console::ConsoleAppender;
 use log4rs::config::{Appender, Config, Root};